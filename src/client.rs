@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use reqwest::{header::HeaderMap, Body};
+use reqwest::{header::HeaderMap, Body, StatusCode};
 use serde::Deserialize;
 use serde_json::Value;
 
@@ -12,6 +12,23 @@ pub mod files;
 pub mod apikey;
 pub mod pullzones;
 pub mod storagezones;
+pub mod transfers;
+pub mod safedelete;
+pub mod fileio;
+pub mod usage;
+pub mod archive;
+pub mod downloadcache;
+pub mod listingcache;
+pub mod lifecycle;
+pub mod versioning;
+pub mod replication;
+pub mod endpointselection;
+pub mod inventory;
+pub mod dedup;
+pub mod publicurl;
+pub mod purge;
+#[cfg(feature = "watch")]
+pub mod watch;
 
 const BUNNY_STORAGE_API_ROOT: &str = "https://api.bunny.net";
 const ENV_BUNNY_STORAGE_API_KEY_NAME: &str = "BUNNYSTORAGE_API_KEY";
@@ -111,6 +128,15 @@ impl BunnyCDNClientConfig {
 pub struct BunnyCDNClient {
 	config: BunnyCDNClientConfig,
 	http_client: reqwest::Client,
+	// Content types by lowercase file extension, which take precedence over the guessed content type
+	content_type_overrides: HashMap<String, String>,
+	download_cache: Option<downloadcache::DownloadCache>,
+	listing_cache: Option<listingcache::ListingCache>,
+	versioning: Option<versioning::VersioningParameters>,
+	endpoint_selector: Option<endpointselection::EndpointSelector>,
+	// The base URL of the linked pull zone, which is looked up once
+	public_url_base: std::sync::Mutex<Option<String>>,
+	purger: Option<purge::Purger>,
 }
 
 pub struct BunnyCDNPageParameters {
@@ -153,8 +179,31 @@ pub struct BunnyCDNPostResponse {
 	pub body: String,
 }
 
+#[derive(Default)]
 pub struct BunnyCDNDataOptions {
 	pub headers: Option<HashMap<String, String>>,
+	// Takes precedence over a Content-Type provided in the headers
+	pub content_type: Option<String>,
+}
+
+impl BunnyCDNDataOptions {
+
+	/// The headers to send, where an explicit content type replaces any Content-Type header
+	fn request_headers(&self) -> Vec<(String, String)> {
+		let mut request_headers: Vec<(String, String)> = Vec::new();
+		if let Some(headers) = &self.headers {
+			for (header_name, header_value) in headers.iter() {
+				if self.content_type.is_some() && header_name.eq_ignore_ascii_case(CONTENT_TYPE_HEADER_NAME) {
+					continue;
+				}
+				request_headers.push((header_name.to_string(), header_value.to_string()));
+			}
+		}
+		if let Some(content_type) = &self.content_type {
+			request_headers.push((CONTENT_TYPE_HEADER_NAME.to_string(), content_type.to_string()));
+		}
+		return request_headers;
+	}
 }
 
 impl BunnyCDNClient {
@@ -163,6 +212,13 @@ impl BunnyCDNClient {
 		let client = BunnyCDNClient{
 			config,
 			http_client: reqwest::Client::new(),
+			content_type_overrides: HashMap::new(),
+			download_cache: None,
+			listing_cache: None,
+			versioning: None,
+			endpoint_selector: None,
+			public_url_base: std::sync::Mutex::new(None),
+			purger: None,
 		};
 		return Ok(client);
 	}
@@ -172,6 +228,23 @@ impl BunnyCDNClient {
 		return Self::new(client_config);
 	}
 
+	pub fn storage_zone_name(&self) -> &str {
+		return &self.config.storage_zone_name;
+	}
+
+	/// Registers the content type to use when uploading files with the provided extension,
+	/// replacing the content type that would otherwise be guessed from the extension.
+	/// The extension is matched case insensitively and may be provided with or without a leading .
+	pub fn set_content_type_override(&mut self, extension: &str, content_type: &str) {
+		let used_extension = extension.trim().trim_start_matches('.').to_lowercase();
+		self.content_type_overrides.insert(used_extension, content_type.trim().to_string());
+	}
+
+	pub fn remove_content_type_override(&mut self, extension: &str) {
+		let used_extension = extension.trim().trim_start_matches('.').to_lowercase();
+		self.content_type_overrides.remove(&used_extension);
+	}
+
 	fn add_page_parameters(&self, params: &mut HashMap<&str, String>, page_params_opt: Option<&BunnyCDNPageParameters>) {
 		if let Some(page_params) = page_params_opt {
 			if let Some(page) = page_params.page {
@@ -245,10 +318,8 @@ impl BunnyCDNClient {
 			.body(data);
 
 		if let Some(provided_options) = options {
-			if let Some(headers) = &provided_options.headers {
-				for (header_name, header_value) in headers.iter() {
-					http_post_request = http_post_request.header(header_name, header_value);
-				}
+			for (header_name, header_value) in provided_options.request_headers().iter() {
+				http_post_request = http_post_request.header(header_name, header_value);
 			}
 		}
 		http_post_request = http_post_request.header(ACCESS_KEY_HEADER_NAME, access_key);
@@ -277,10 +348,8 @@ impl BunnyCDNClient {
 			.body(data);
 
 		if let Some(provided_options) = options {
-			if let Some(headers) = &provided_options.headers {
-				for (header_name, header_value) in headers.iter() {
-					http_put_request = http_put_request.header(header_name, header_value);
-				}
+			for (header_name, header_value) in provided_options.request_headers().iter() {
+				http_put_request = http_put_request.header(header_name, header_value);
 			}
 		}
 		http_put_request = http_put_request.header(ACCESS_KEY_HEADER_NAME, access_key);
@@ -301,12 +370,23 @@ impl BunnyCDNClient {
 	}
 
 	async fn delete(&self, url: &str, access_key: &str) -> Result<(), Error> {
+		if !self.delete_if_exists(url, access_key).await? {
+			return Err(Error::new_from_message(&format!("Failed Deleting {} - Not Found", url)));
+		}
+		return Ok(());
+	}
+
+	// Returns false instead of an error if nothing exists at the url
+	async fn delete_if_exists(&self, url: &str, access_key: &str) -> Result<bool, Error> {
 		let http_delete_response = self.http_client.delete(url)
 			.header(ACCESS_KEY_HEADER_NAME, access_key)
 			.send()
 			.await
-			.map_err(|http_delete_error| Error::new_from_message(&http_delete_error.to_string()))?
-			.error_for_status()
+			.map_err(|http_delete_error| Error::new_from_message(&http_delete_error.to_string()))?;
+		if http_delete_response.status() == StatusCode::NOT_FOUND {
+			return Ok(false);
+		}
+		let http_delete_response = http_delete_response.error_for_status()
 			.map_err(|http_delete_request_error| Error::new_from_message(&http_delete_request_error.to_string()))?;
 
 		let http_delete_content = http_delete_response.text()
@@ -314,11 +394,26 @@ impl BunnyCDNClient {
 			.map_err(|http_delete_content_error| Error::new_from_message(&http_delete_content_error.to_string()))?;
 
 		self.attempt_parse_request_error(&http_delete_content)?;
-		return Ok(());
+		return Ok(true);
 	}
 
 }
 
+#[cfg(test)]
+impl BunnyCDNClientConfig {
+
+	// A configuration which passes validation, for tests which never reach Bunnystorage
+	pub(crate) fn new_offline() -> BunnyCDNClientConfig {
+		return BunnyCDNClientConfig{
+			api_key: "api_key".to_string(),
+			read_password: "read_password".to_string(),
+			write_password: None,
+			endpoint: StorageEndpoint::Falkenstein,
+			storage_zone_name: "storage_zone".to_string(),
+		};
+	}
+}
+
 #[cfg(test)]
 mod client_tests {
 	use super::BunnyCDNClient;
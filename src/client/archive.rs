@@ -0,0 +1,383 @@
+use async_compression::tokio::{bufread::{GzipDecoder, ZstdDecoder}, write::{GzipEncoder, ZstdEncoder}};
+use futures::StreamExt;
+use tar::{EntryType, Header};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::{errors::Error, models::file::File, remotepath::RemotePath};
+
+use super::BunnyCDNClient;
+
+const TAR_BLOCK_SIZE: u64 = 512;
+// The name used by GNU tar for the entry holding a path longer than the header allows
+const TAR_GNU_LONG_NAME_PATH: &str = "././@LongLink";
+const TAR_HEADER_NAME_SIZE: usize = 100;
+const TAR_FILE_MODE: u32 = 0o644;
+// The largest GNU long name or PAX extended header which is read into memory. The size comes from
+// the archive itself, so it cannot be trusted
+const TAR_MAX_METADATA_SIZE: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TarCompression {
+	#[default]
+	None,
+	Gzip,
+	Zstd,
+}
+
+#[derive(Debug, Default)]
+pub struct ArchiveReport {
+	// The paths of the files which were archived or uploaded, relative to the root of the storage zone
+	pub files: Vec<String>,
+	pub total_bytes: u64,
+}
+
+// A file entry read from a tar stream. The contents follow directly in the stream
+#[derive(Debug, PartialEq)]
+struct TarFileEntry {
+	path: String,
+	size: u64,
+}
+
+fn map_archive_io_error(io_error: std::io::Error) -> Error {
+	return Error::new_from_message(&format!("Failed Processing Archive - Error: {}", io_error));
+}
+
+fn get_tar_padding(size: u64) -> u64 {
+	return (TAR_BLOCK_SIZE - size % TAR_BLOCK_SIZE) % TAR_BLOCK_SIZE;
+}
+
+fn new_tar_header(entry_path: &str, size: u64, mtime: u64, entry_type: EntryType) -> Header {
+	let mut header = Header::new_gnu();
+	let entry_path_bytes = entry_path.as_bytes();
+	let header_name_length = entry_path_bytes.len().min(TAR_HEADER_NAME_SIZE);
+	// Names longer than the header are truncated here and preceded by a GNU long name entry
+	header.as_old_mut().name[..header_name_length].copy_from_slice(&entry_path_bytes[..header_name_length]);
+	header.set_size(size);
+	header.set_mode(TAR_FILE_MODE);
+	header.set_mtime(mtime);
+	header.set_entry_type(entry_type);
+	header.set_cksum();
+	return header;
+}
+
+async fn write_tar_padding<W: AsyncWrite + Unpin + ?Sized>(writer: &mut W, size: u64) -> Result<(), Error> {
+	let padding = vec![0u8; get_tar_padding(size) as usize];
+	writer.write_all(&padding).await.map_err(map_archive_io_error)?;
+	return Ok(());
+}
+
+async fn write_tar_file_header<W: AsyncWrite + Unpin + ?Sized>(writer: &mut W, entry_path: &str, size: u64, mtime: u64) -> Result<(), Error> {
+	if entry_path.len() > TAR_HEADER_NAME_SIZE {
+		let mut long_name = entry_path.as_bytes().to_vec();
+		long_name.push(0);
+		let long_name_header = new_tar_header(TAR_GNU_LONG_NAME_PATH, long_name.len() as u64, 0, EntryType::GNULongName);
+		writer.write_all(long_name_header.as_bytes()).await.map_err(map_archive_io_error)?;
+		writer.write_all(&long_name).await.map_err(map_archive_io_error)?;
+		write_tar_padding(writer, long_name.len() as u64).await?;
+	}
+	let file_header = new_tar_header(entry_path, size, mtime, EntryType::Regular);
+	writer.write_all(file_header.as_bytes()).await.map_err(map_archive_io_error)?;
+	return Ok(());
+}
+
+async fn write_tar_end<W: AsyncWrite + Unpin + ?Sized>(writer: &mut W) -> Result<(), Error> {
+	let end_blocks = vec![0u8; 2 * TAR_BLOCK_SIZE as usize];
+	writer.write_all(&end_blocks).await.map_err(map_archive_io_error)?;
+	return Ok(());
+}
+
+async fn skip_tar_data<R: AsyncRead + Unpin + ?Sized>(reader: &mut R, size: u64) -> Result<(), Error> {
+	let skipped_size = tokio::io::copy(&mut reader.take(size), &mut tokio::io::sink()).await.map_err(map_archive_io_error)?;
+	if skipped_size != size {
+		return Err(Error::new_from_message("Failed Processing Archive - Unexpected end of archive"));
+	}
+	return Ok(());
+}
+
+async fn read_tar_data<R: AsyncRead + Unpin + ?Sized>(reader: &mut R, size: u64) -> Result<Vec<u8>, Error> {
+	if size > TAR_MAX_METADATA_SIZE {
+		return Err(Error::new_from_message(&format!(
+			"Failed Processing Archive - Extended header of {} bytes exceeds the limit of {} bytes",
+			size,
+			TAR_MAX_METADATA_SIZE,
+		)));
+	}
+	let mut data = vec![0u8; size as usize];
+	reader.read_exact(&mut data).await.map_err(map_archive_io_error)?;
+	skip_tar_data(reader, get_tar_padding(size)).await?;
+	return Ok(data);
+}
+
+// The records of a PAX extended header which override the header of the following entry
+#[derive(Debug, Default, PartialEq)]
+struct PaxExtensions {
+	path: Option<String>,
+	// Sizes of 8 GiB and above do not fit into the header
+	size: Option<u64>,
+}
+
+fn map_pax_record_error(pax_record_offset: usize) -> Error {
+	return Error::new_from_message(&format!("Failed Processing Archive - Invalid PAX record at offset {}", pax_record_offset));
+}
+
+// Parses PAX extended header data, which consists of "<length> <key>=<value>\n" records. The length covers
+// the whole record, so values may contain newlines
+fn parse_pax_extensions(pax_data: &[u8]) -> Result<PaxExtensions, Error> {
+	let mut pax_extensions = PaxExtensions::default();
+	let mut pax_record_offset: usize = 0;
+	while pax_record_offset < pax_data.len() {
+		let pax_record_data = &pax_data[pax_record_offset..];
+		let length_end = pax_record_data.iter().position(|pax_byte| *pax_byte == b' ')
+			.ok_or_else(|| map_pax_record_error(pax_record_offset))?;
+		let pax_record_length: usize = std::str::from_utf8(&pax_record_data[..length_end]).ok()
+			.and_then(|pax_record_length| pax_record_length.parse().ok())
+			.filter(|pax_record_length| *pax_record_length > length_end + 1 && *pax_record_length <= pax_record_data.len())
+			.ok_or_else(|| map_pax_record_error(pax_record_offset))?;
+		let pax_record = &pax_record_data[length_end + 1..pax_record_length];
+		let pax_key_value = pax_record.strip_suffix(b"\n")
+			.ok_or_else(|| map_pax_record_error(pax_record_offset))?;
+		let key_end = pax_key_value.iter().position(|pax_byte| *pax_byte == b'=')
+			.ok_or_else(|| map_pax_record_error(pax_record_offset))?;
+		let pax_value = &pax_key_value[key_end + 1..];
+		match &pax_key_value[..key_end] {
+			b"path" => pax_extensions.path = Some(String::from_utf8_lossy(pax_value).to_string()),
+			b"size" => pax_extensions.size = Some(std::str::from_utf8(pax_value).ok()
+				.and_then(|pax_size| pax_size.parse().ok())
+				.ok_or_else(|| map_pax_record_error(pax_record_offset))?),
+			_ => {},
+		}
+		pax_record_offset += pax_record_length;
+	}
+	return Ok(pax_extensions);
+}
+
+/// Reads headers until the next regular file, skipping directories, links and other entries.
+/// Returns None at the end of the archive
+async fn read_next_tar_file_entry<R: AsyncRead + Unpin + ?Sized>(reader: &mut R) -> Result<Option<TarFileEntry>, Error> {
+	let mut pending_path: Option<String> = None;
+	let mut pending_size: Option<u64> = None;
+	let mut header_bytes = [0u8; TAR_BLOCK_SIZE as usize];
+	loop {
+		let read_header_result = reader.read_exact(&mut header_bytes).await;
+		match read_header_result {
+			Err(read_header_error) if read_header_error.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+			Err(read_header_error) => return Err(map_archive_io_error(read_header_error)),
+			Ok(_) => {},
+		}
+		// The archive ends with empty blocks
+		if header_bytes.iter().all(|header_byte| *header_byte == 0) {
+			return Ok(None);
+		}
+		let header = Header::from_byte_slice(&header_bytes);
+		let entry_size = header.entry_size().map_err(map_archive_io_error)?;
+		let entry_type = header.entry_type();
+		if entry_type.is_gnu_longname() {
+			let long_name = read_tar_data(reader, entry_size).await?;
+			pending_path = Some(String::from_utf8_lossy(&long_name).trim_end_matches('\0').to_string());
+			continue;
+		}
+		if entry_type.is_pax_local_extensions() {
+			let pax_extensions = parse_pax_extensions(&read_tar_data(reader, entry_size).await?)?;
+			if let Some(pax_path) = pax_extensions.path {
+				pending_path = Some(pax_path);
+			}
+			if let Some(pax_size) = pax_extensions.size {
+				pending_size = Some(pax_size);
+			}
+			continue;
+		}
+		let entry_path = match pending_path.take() {
+			Some(pending_path) => pending_path,
+			None => String::from_utf8_lossy(&header.path_bytes()).to_string(),
+		};
+		let entry_size = pending_size.take().unwrap_or(entry_size);
+		if !(entry_type.is_file() || entry_type.is_contiguous()) || entry_path.ends_with('/') {
+			skip_tar_data(reader, entry_size + get_tar_padding(entry_size)).await?;
+			continue;
+		}
+		return Ok(Some(TarFileEntry{
+			path: entry_path,
+			size: entry_size,
+		}));
+	}
+}
+
+impl BunnyCDNClient {
+
+	async fn write_tar_file_entry<W: AsyncWrite + Unpin + ?Sized>(&self, writer: &mut W, entry_path: &str, file: &File) -> Result<(), Error> {
+		let remote_filepath = file.to_remote_path()?;
+		let mtime = file.last_changed().timestamp().max(0) as u64;
+		write_tar_file_header(writer, entry_path, file.length(), mtime).await?;
+		let download_file_response = self.open_remote_file_response(&remote_filepath).await?;
+		let mut download_file_stream = download_file_response.bytes_stream();
+		let mut written_size: u64 = 0;
+		while let Some(file_item_result) = download_file_stream.next().await {
+			let file_item_content = file_item_result
+				.map_err(|stream_error| Error::new_from_message(&format!("Failed Stream Contents - Error: {}", stream_error)))?;
+			written_size += file_item_content.len() as u64;
+			if written_size > file.length() {
+				break;
+			}
+			writer.write_all(&file_item_content).await.map_err(map_archive_io_error)?;
+		}
+		// The size in the header has already been written, so a file changed since listing cannot be archived
+		if written_size != file.length() {
+			return Err(Error::new_from_message(&format!("Failed Archiving {} - The file changed while archiving", remote_filepath)));
+		}
+		return write_tar_padding(writer, written_size).await;
+	}
+
+	/// Streams every file below the prefix into a tar archive, without buffering to disk.
+	/// Paths in the archive are relative to the prefix. The writer is shut down once the archive
+	/// is complete, which is required to finish the compressed stream.
+	///
+	/// Parameters:
+	/// * prefix: The directory to archive. An empty prefix or / archives the entire storage zone
+	/// * writer: Where the archive is written to e.g. a local file
+	/// * compression: The compression applied to the entire archive
+	pub async fn download_directory_as_tar<W: AsyncWrite + Unpin + Send>(&self, prefix: &str, writer: W, compression: TarCompression) -> Result<ArchiveReport, Error> {
+		let remote_directory = RemotePath::directory(prefix)?;
+		let directory_files = self.get_files_recursive(&remote_directory.to_string()).await?;
+		let mut archive_writer: Box<dyn AsyncWrite + Unpin + Send + '_> = match compression {
+			TarCompression::None => Box::new(writer),
+			TarCompression::Gzip => Box::new(GzipEncoder::new(writer)),
+			TarCompression::Zstd => Box::new(ZstdEncoder::new(writer)),
+		};
+		let mut archive_report = ArchiveReport::default();
+		for directory_file in directory_files.iter() {
+			let remote_filepath = directory_file.to_remote_path()?;
+			let Some(entry_path) = remote_filepath.strip_prefix(&remote_directory) else {
+				continue;
+			};
+			self.write_tar_file_entry(&mut archive_writer, &entry_path, directory_file).await?;
+			archive_report.files.push(remote_filepath.to_string());
+			archive_report.total_bytes += directory_file.length();
+		}
+		write_tar_end(&mut archive_writer).await?;
+		archive_writer.shutdown().await.map_err(map_archive_io_error)?;
+		return Ok(archive_report);
+	}
+
+	/// Expands a tar archive into individual uploads below the prefix. Each file is streamed
+	/// directly from the archive into its upload. Directories, links and other special entries are skipped.
+	/// Entries which would end up outside the prefix, e.g. using .., are rejected.
+	///
+	/// Parameters:
+	/// * reader: The archive e.g. a local file
+	/// * prefix: The directory the files of the archive are uploaded to
+	/// * compression: The compression applied to the entire archive
+	pub async fn upload_tar<R: AsyncRead + Unpin + Send>(&self, reader: R, prefix: &str, compression: TarCompression) -> Result<ArchiveReport, Error> {
+		self.check_write_password_ok()?;
+		let remote_directory = RemotePath::directory(prefix)?;
+		let mut archive_reader: Box<dyn AsyncRead + Unpin + Send + '_> = match compression {
+			TarCompression::None => Box::new(reader),
+			TarCompression::Gzip => Box::new(GzipDecoder::new(BufReader::new(reader))),
+			TarCompression::Zstd => Box::new(ZstdDecoder::new(BufReader::new(reader))),
+		};
+		let purge_batch = self.begin_purge_batch();
+		let mut archive_report = ArchiveReport::default();
+		while let Some(tar_file_entry) = read_next_tar_file_entry(&mut archive_reader).await? {
+			let remote_filepath = remote_directory.join(&tar_file_entry.path)?;
+			let mut remote_file_writer = self.open_write(&remote_filepath.to_string())?;
+			let uploaded_size = tokio::io::copy(&mut (&mut archive_reader).take(tar_file_entry.size), &mut remote_file_writer)
+				.await
+				.map_err(map_archive_io_error)?;
+			if uploaded_size != tar_file_entry.size {
+				return Err(Error::new_from_message("Failed Processing Archive - Unexpected end of archive"));
+			}
+			remote_file_writer.shutdown().await
+				.map_err(|upload_error| Error::new_from_message(&format!("Failed Uploading {} - Error: {}", remote_filepath, upload_error)))?;
+			skip_tar_data(&mut archive_reader, get_tar_padding(tar_file_entry.size)).await?;
+			archive_report.files.push(remote_filepath.to_string());
+			archive_report.total_bytes += tar_file_entry.size;
+		}
+		purge_batch.finish_checked().await?;
+		return Ok(archive_report);
+	}
+
+}
+
+#[cfg(test)]
+mod archive_tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn test_tar_entries_round_trip() {
+		let long_entry_path = format!("{}/report.csv", "nested".repeat(20));
+		let mut archive: Vec<u8> = Vec::new();
+		write_tar_file_header(&mut archive, "images/logo.png", 3, 0).await.unwrap();
+		archive.extend_from_slice(b"png");
+		write_tar_padding(&mut archive, 3).await.unwrap();
+		write_tar_file_header(&mut archive, &long_entry_path, 0, 0).await.unwrap();
+		write_tar_end(&mut archive).await.unwrap();
+		assert_eq!(archive.len() as u64 % TAR_BLOCK_SIZE, 0);
+		let mut archive_reader: &[u8] = &archive;
+		let first_entry = read_next_tar_file_entry(&mut archive_reader).await.unwrap();
+		assert_eq!(first_entry, Some(TarFileEntry{ path: "images/logo.png".to_string(), size: 3 }));
+		let first_entry_data = read_tar_data(&mut archive_reader, 3).await.unwrap();
+		assert_eq!(first_entry_data, b"png");
+		let second_entry = read_next_tar_file_entry(&mut archive_reader).await.unwrap();
+		assert_eq!(second_entry, Some(TarFileEntry{ path: long_entry_path, size: 0 }));
+		assert!(read_next_tar_file_entry(&mut archive_reader).await.is_ok_and(|tar_file_entry| tar_file_entry.is_none()));
+	}
+
+	#[test]
+	fn test_parse_pax_extensions() {
+		let pax_extensions = parse_pax_extensions(b"30 mtime=1700000000.000000000\n26 path=images/banner.png\n16 size=9000000\n");
+		assert!(pax_extensions.is_ok_and(|pax_extensions| pax_extensions == PaxExtensions{
+			path: Some("images/banner.png".to_string()),
+			size: Some(9000000),
+		}));
+		// The record length is used instead of the newline, which may be part of the path
+		let newline_pax_extensions = parse_pax_extensions(b"22 path=notes\nold.txt\n");
+		assert!(newline_pax_extensions.is_ok_and(|pax_extensions| pax_extensions.path == Some("notes\nold.txt".to_string())));
+		assert!(parse_pax_extensions(b"99 path=images/banner.png\n").is_err());
+		assert!(parse_pax_extensions(b"17 size=9000000x\n").is_err());
+	}
+
+	#[tokio::test]
+	async fn test_pax_size_overrides_header() {
+		let pax_data = b"11 size=10\n";
+		let pax_header = new_tar_header("PaxHeader", pax_data.len() as u64, 0, EntryType::XHeader);
+		let mut archive: Vec<u8> = pax_header.as_bytes().to_vec();
+		archive.extend_from_slice(pax_data);
+		write_tar_padding(&mut archive, pax_data.len() as u64).await.unwrap();
+		// The header claims no data, as for entries too large for the size field
+		write_tar_file_header(&mut archive, "data.bin", 0, 0).await.unwrap();
+		archive.extend_from_slice(b"0123456789");
+		write_tar_padding(&mut archive, 10).await.unwrap();
+		write_tar_file_header(&mut archive, "next.txt", 0, 0).await.unwrap();
+		write_tar_end(&mut archive).await.unwrap();
+		let mut archive_reader: &[u8] = &archive;
+		let first_entry = read_next_tar_file_entry(&mut archive_reader).await.unwrap();
+		assert_eq!(first_entry, Some(TarFileEntry{ path: "data.bin".to_string(), size: 10 }));
+		assert!(read_tar_data(&mut archive_reader, 10).await.is_ok_and(|entry_data| entry_data == b"0123456789"));
+		let second_entry = read_next_tar_file_entry(&mut archive_reader).await.unwrap();
+		assert_eq!(second_entry, Some(TarFileEntry{ path: "next.txt".to_string(), size: 0 }));
+	}
+
+	#[tokio::test]
+	async fn test_reject_oversized_tar_metadata() {
+		// A long name entry claiming far more data than any path needs
+		let long_name_header = new_tar_header(TAR_GNU_LONG_NAME_PATH, u64::MAX >> 8, 0, EntryType::GNULongName);
+		let mut archive: Vec<u8> = long_name_header.as_bytes().to_vec();
+		write_tar_end(&mut archive).await.unwrap();
+		let mut archive_reader: &[u8] = &archive;
+		assert!(read_next_tar_file_entry(&mut archive_reader).await.is_err());
+	}
+
+	#[tokio::test]
+	async fn test_download_and_upload_tar() {
+		let client_result: Result<BunnyCDNClient, Error> = BunnyCDNClient::new_from_env();
+		assert!(client_result.is_ok());
+		let client = client_result.unwrap();
+		let test_upload_result = client.upload_file("./tests/files/source/Test_Image.jpg", Some("/tests/files/archive/source/Test_Image.jpg")).await;
+		assert!(test_upload_result.is_ok());
+		let mut test_archive: Vec<u8> = Vec::new();
+		let test_download_result = client.download_directory_as_tar("/tests/files/archive/source/", &mut test_archive, TarCompression::Gzip).await;
+		assert!(test_download_result.is_ok_and(|archive_report| archive_report.files.len() == 1));
+		let test_upload_tar_result = client.upload_tar(test_archive.as_slice(), "/tests/files/archive/target/", TarCompression::Gzip).await;
+		assert!(test_upload_tar_result.is_ok_and(|archive_report| archive_report.files == vec!["tests/files/archive/target/Test_Image.jpg".to_string()]));
+		assert!(client.delete_directory("/tests/files/archive/").await.is_ok());
+	}
+}
@@ -0,0 +1,273 @@
+use std::{collections::{BTreeMap, HashMap}, path::Path};
+
+use futures::StreamExt;
+use sha2::{Digest, Sha256};
+use tokio::fs;
+
+use crate::{errors::Error, models::{file::{File, FileChecksum}, storageendpoint::StorageEndpoint}, remotepath::RemotePath};
+
+use super::{safedelete::DeleteFailure, BunnyCDNClient};
+
+pub struct DedupParameters {
+	// Download files without a checksum to compute it. Otherwise these files are skipped
+	pub compute_missing_checksums: bool,
+	// Files smaller than this are ignored, since replacing them saves little
+	pub min_length: u64,
+}
+
+impl Default for DedupParameters {
+
+	fn default() -> Self {
+		return DedupParameters{
+			compute_missing_checksums: true,
+			min_length: 1,
+		};
+	}
+}
+
+/// Files with identical content
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+	pub checksum: FileChecksum,
+	// The length of each of the files
+	pub length: u64,
+	// The file which is kept when replacing duplicates. The oldest file, or the first path if created at the same time
+	pub canonical: String,
+	// Every other file with the same content, sorted by path
+	pub duplicates: Vec<String>,
+}
+
+impl DuplicateGroup {
+
+	/// The bytes which are freed by keeping only the canonical file
+	pub fn wasted_bytes(&self) -> u64 {
+		return self.length * self.duplicates.len() as u64;
+	}
+}
+
+#[derive(Debug, Default)]
+pub struct DedupReport {
+	pub prefix: String,
+	pub scanned_files: u64,
+	// The number of files which were downloaded since Bunnystorage had no checksum for them
+	pub computed_checksums: u64,
+	// The files without a checksum which were not downloaded
+	pub skipped: Vec<String>,
+	// Sorted by wasted bytes in descending order
+	pub groups: Vec<DuplicateGroup>,
+}
+
+impl DedupReport {
+
+	pub fn wasted_bytes(&self) -> u64 {
+		return self.groups.iter().map(|group| group.wasted_bytes()).sum();
+	}
+}
+
+#[derive(Debug, Default)]
+pub struct DedupReplaceReport {
+	// The duplicates which were deleted
+	pub removed: Vec<String>,
+	// The duplicates which were kept since they could not be verified or deleted
+	pub failed: Vec<DeleteFailure>,
+}
+
+/// Groups the files by checksum, keeping only the groups with more than one file
+pub(crate) fn group_duplicate_files(files: &[(File, FileChecksum)]) -> Vec<DuplicateGroup> {
+	let mut files_by_checksum: HashMap<(FileChecksum, u64), Vec<&File>> = HashMap::new();
+	for (file, checksum) in files.iter() {
+		files_by_checksum.entry((*checksum, file.length())).or_default().push(file);
+	}
+	let mut groups: Vec<DuplicateGroup> = Vec::new();
+	for ((checksum, length), mut group_files) in files_by_checksum.into_iter() {
+		if group_files.len() < 2 {
+			continue;
+		}
+		group_files.sort_by(|file, other_file| file.date_created().cmp(&other_file.date_created())
+			.then_with(|| file.remote_path().cmp(&other_file.remote_path())));
+		let canonical = group_files.remove(0).remote_path();
+		let mut duplicates: Vec<String> = group_files.iter().map(|file| file.remote_path()).collect();
+		duplicates.sort();
+		groups.push(DuplicateGroup{ checksum, length, canonical, duplicates });
+	}
+	groups.sort_by(|group, other_group| other_group.wasted_bytes().cmp(&group.wasted_bytes())
+		.then_with(|| group.canonical.cmp(&other_group.canonical)));
+	return groups;
+}
+
+impl BunnyCDNClient {
+
+	/// Computes the SHA256 checksum of a remote file by streaming its content from the given endpoint
+	pub(crate) async fn compute_remote_checksum(&self, endpoint: &StorageEndpoint, remote_filepath: &RemotePath) -> Result<FileChecksum, Error> {
+		let file_response = self.open_endpoint_file_response(endpoint, remote_filepath).await?;
+		let mut file_stream = file_response.bytes_stream();
+		let mut file_hasher = Sha256::new();
+		while let Some(file_chunk_result) = file_stream.next().await {
+			let file_chunk = file_chunk_result
+				.map_err(|stream_error| Error::new_from_message(&format!("Failed Stream Contents - Error: {}", stream_error)))?;
+			file_hasher.update(&file_chunk);
+		}
+		return Ok(FileChecksum::Sha256(file_hasher.finalize().into()));
+	}
+
+	/// Finds files below the prefix with identical content. Nothing is changed, see replace_duplicate_files
+	///
+	/// Parameters:
+	/// * prefix: The directory to search, including its subdirectories
+	/// * params: Whether to compute missing checksums and the minimum length
+	pub async fn find_duplicate_files(&self, prefix: &str, params: &DedupParameters) -> Result<DedupReport, Error> {
+		let remote_directory = RemotePath::directory(prefix)?;
+		let files = self.get_files_recursive(&remote_directory.to_string()).await?;
+		let mut dedup_report = DedupReport{
+			prefix: remote_directory.to_string(),
+			scanned_files: files.len() as u64,
+			..Default::default()
+		};
+		// Only files sharing their length with another file can be duplicates, so nothing else is downloaded
+		let mut length_counts: HashMap<u64, usize> = HashMap::new();
+		for file in files.iter() {
+			*length_counts.entry(file.length()).or_default() += 1;
+		}
+		let read_endpoint = self.read_endpoint().await;
+		let mut checksummed_files: Vec<(File, FileChecksum)> = Vec::new();
+		for file in files.into_iter() {
+			if file.length() < params.min_length || length_counts.get(&file.length()).is_none_or(|length_count| *length_count < 2) {
+				continue;
+			}
+			let checksum = match file.parsed_checksum() {
+				Some(checksum) => checksum,
+				None if params.compute_missing_checksums => {
+					dedup_report.computed_checksums += 1;
+					self.compute_remote_checksum(&read_endpoint, &file.to_remote_path()?).await?
+				},
+				None => {
+					dedup_report.skipped.push(file.remote_path());
+					continue;
+				},
+			};
+			checksummed_files.push((file, checksum));
+		}
+		dedup_report.groups = group_duplicate_files(&checksummed_files);
+		return Ok(dedup_report);
+	}
+
+	/// Deletes the duplicates of each group, keeping the canonical file. Before anything is deleted,
+	/// a JSON object mapping each duplicate path to its canonical path is written to the mapping file,
+	/// so references to the removed paths can be rewritten. A duplicate is only deleted if its
+	/// checksum still matches the group, and a group is skipped if its canonical file no longer matches
+	///
+	/// Parameters:
+	/// * dedup_report: The report returned by find_duplicate_files
+	/// * mapping_path: The local file the mapping is written to
+	pub async fn replace_duplicate_files(&self, dedup_report: &DedupReport, mapping_path: &str) -> Result<DedupReplaceReport, Error> {
+		self.check_write_password_ok()?;
+		let mut duplicate_mapping: BTreeMap<&str, &str> = BTreeMap::new();
+		for group in dedup_report.groups.iter() {
+			for duplicate in group.duplicates.iter() {
+				duplicate_mapping.insert(duplicate, &group.canonical);
+			}
+		}
+		let mapping_content = serde_json::to_vec_pretty(&duplicate_mapping)
+			.map_err(|serialize_error| Error::new_from_message(&format!("Failed Serializing Duplicate Mapping - Error: {}", serialize_error)))?;
+		fs::write(Path::new(mapping_path), mapping_content).await
+			.map_err(|write_error| Error::new_from_message(&format!("Failed Writing Duplicate Mapping {} - Error: {}", mapping_path, write_error)))?;
+		let purge_batch = self.begin_purge_batch();
+		let mut replace_report = DedupReplaceReport::default();
+		for group in dedup_report.groups.iter() {
+			// The duplicates are only safe to delete while the canonical file still holds their content
+			if let Err(canonical_error) = self.verify_group_file(&group.canonical, group).await {
+				for duplicate in group.duplicates.iter() {
+					replace_report.failed.push(DeleteFailure{
+						path: duplicate.clone(),
+						error: Error::new_from_message(&format!("Canonical Not Verified - {}", canonical_error)),
+					});
+				}
+				continue;
+			}
+			for duplicate in group.duplicates.iter() {
+				let replace_result = self.verify_and_delete_duplicate(duplicate, group).await;
+				match replace_result {
+					Ok(_) => replace_report.removed.push(duplicate.clone()),
+					Err(replace_error) => replace_report.failed.push(DeleteFailure{
+						path: duplicate.clone(),
+						error: replace_error,
+					}),
+				}
+			}
+		}
+		purge_batch.finish_checked().await?;
+		return Ok(replace_report);
+	}
+
+	/*
+		Checks on the configured endpoint that a file of the group still exists with the checksum and length of the group.
+		Parameters:
+			filepath: The canonical or a duplicate of the group
+			group: The group the file was found in
+	*/
+	async fn verify_group_file(&self, filepath: &str, group: &DuplicateGroup) -> Result<(), Error> {
+		let remote_filepath = RemotePath::file(filepath)?;
+		let Some(remote_file) = self.find_primary_entry(&remote_filepath).await? else {
+			return Err(Error::new_from_message(&format!("File No Longer Exists - {}", filepath)));
+		};
+		if remote_file.length() != group.length {
+			return Err(Error::new_from_message(&format!("File Has Changed - {} no longer matches the group of {}", filepath, group.canonical)));
+		}
+		let remote_checksum = match remote_file.parsed_checksum() {
+			Some(remote_checksum) => remote_checksum,
+			None => self.compute_remote_checksum(&self.config.endpoint, &remote_filepath).await?,
+		};
+		if remote_checksum != group.checksum {
+			return Err(Error::new_from_message(&format!("File Has Changed - {} no longer matches the group of {}", filepath, group.canonical)));
+		}
+		return Ok(());
+	}
+
+	async fn verify_and_delete_duplicate(&self, duplicate: &str, group: &DuplicateGroup) -> Result<(), Error> {
+		self.verify_group_file(duplicate, group).await?;
+		return self.delete_file(duplicate).await;
+	}
+
+}
+
+#[cfg(test)]
+mod dedup_tests {
+	use crate::models::file::TestFileBuilder;
+	use super::*;
+
+	#[test]
+	fn test_group_duplicate_files() {
+		let checksum = FileChecksum::Sha256([1; 32]);
+		let other_checksum = FileChecksum::Sha256([2; 32]);
+		let test_files = vec![
+			(TestFileBuilder::new("uploads/b.jpg").length(100).date_created("2024-01-02T00:00:00").build(), checksum),
+			(TestFileBuilder::new("uploads/a.jpg").length(100).date_created("2024-01-03T00:00:00").build(), checksum),
+			(TestFileBuilder::new("uploads/original.jpg").length(100).date_created("2024-01-01T00:00:00").build(), checksum),
+			(TestFileBuilder::new("uploads/unique.jpg").length(100).date_created("2024-01-01T00:00:00").build(), other_checksum),
+			(TestFileBuilder::new("uploads/small.txt").length(10).date_created("2024-01-01T00:00:00").build(), other_checksum),
+			(TestFileBuilder::new("uploads/small-copy.txt").length(10).date_created("2024-01-01T00:00:00").build(), other_checksum),
+		];
+		let groups = group_duplicate_files(&test_files);
+		assert_eq!(groups.len(), 2);
+		assert_eq!(groups[0].canonical, "uploads/original.jpg");
+		assert_eq!(groups[0].duplicates, vec!["uploads/a.jpg", "uploads/b.jpg"]);
+		assert_eq!(groups[0].wasted_bytes(), 200);
+		assert_eq!(groups[1].canonical, "uploads/small-copy.txt");
+		assert_eq!(groups[1].wasted_bytes(), 10);
+	}
+
+	#[tokio::test]
+	async fn test_find_duplicate_files() {
+		let client_result: Result<BunnyCDNClient, Error> = BunnyCDNClient::new_from_env();
+		assert!(client_result.is_ok());
+		let client = client_result.unwrap();
+		assert!(client.upload_file_content("tests/files/dedup/original.txt", b"duplicate".to_vec(), None).await.is_ok());
+		assert!(client.upload_file_content("tests/files/dedup/copy.txt", b"duplicate".to_vec(), None).await.is_ok());
+		let dedup_report_result = client.find_duplicate_files("tests/files/dedup/", &DedupParameters::default()).await;
+		assert!(dedup_report_result.is_ok());
+		let dedup_report = dedup_report_result.unwrap();
+		assert_eq!(dedup_report.groups.len(), 1);
+		assert_eq!(dedup_report.wasted_bytes(), 9);
+		assert!(client.delete_directory("tests/files/dedup/").await.is_ok());
+	}
+}
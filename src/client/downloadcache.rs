@@ -0,0 +1,311 @@
+use std::{io::ErrorKind, path::{Path, PathBuf}, time::{Duration, SystemTime}};
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use sha2::{Digest, Sha256};
+use tokio::{fs, io::{AsyncReadExt, AsyncWriteExt, BufWriter}};
+
+use crate::{errors::Error, models::file::{File, FileChecksum}, remotepath::RemotePath};
+
+use super::BunnyCDNClient;
+
+const DOWNLOAD_CACHE_DATA_EXTENSION: &str = "data";
+const DOWNLOAD_CACHE_TEMP_EXTENSION: &str = "tmp";
+const DOWNLOAD_CACHE_LOCK_FILENAME: &str = ".lock";
+// Temporary files older than this are left over by a process which stopped while downloading
+const DOWNLOAD_CACHE_STALE_TEMP_AGE: Duration = Duration::from_secs(60 * 60);
+
+fn map_cache_io_error(io_error: std::io::Error) -> Error {
+	return Error::new_from_message(&format!("Failed Accessing Download Cache - Error: {}", io_error));
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+	return bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+}
+
+/// A cache of downloaded files on the local disk, shared by every client using the same directory.
+///
+/// Each cached file is named after the storage zone and path together with the length, last changed
+/// and checksum reported by Bunnystorage. A cached file is therefore only used while those are unchanged,
+/// and a changed remote file replaces the stale copy. Files are written to a temporary file and renamed
+/// into place, so concurrent processes never observe partially written files.
+/// When the cache exceeds max_bytes the least recently used files are removed.
+#[derive(Debug, Clone)]
+pub struct DownloadCache {
+	directory: PathBuf,
+	max_bytes: u64,
+}
+
+impl DownloadCache {
+
+	/// Parameters:
+	/// * directory: The directory containing the cached files. It is created when needed
+	/// * max_bytes: The maximum total size of the cached files
+	pub fn new<P: Into<PathBuf>>(directory: P, max_bytes: u64) -> DownloadCache {
+		return DownloadCache{
+			directory: directory.into(),
+			max_bytes,
+		};
+	}
+
+	pub fn directory(&self) -> &Path {
+		return &self.directory;
+	}
+
+	pub fn max_bytes(&self) -> u64 {
+		return self.max_bytes;
+	}
+
+	// The key identifies the remote file, whereas the version identifies its current contents
+	fn get_cache_key_and_version(&self, storage_zone_name: &str, file: &File) -> Result<(String, String), Error> {
+		let remote_filepath = file.to_remote_path()?;
+		let cache_key = Sha256::digest(format!("{}/{}", storage_zone_name, remote_filepath).as_bytes());
+		let cache_version = Sha256::digest(format!(
+			"{}:{}:{}",
+			file.length(),
+			file.last_changed().to_rfc3339(),
+			file.parsed_checksum().map(|checksum| checksum.to_hex()).unwrap_or_default(),
+		).as_bytes());
+		return Ok((to_hex(&cache_key), to_hex(&cache_version[..16])));
+	}
+
+	fn get_cache_filepath(&self, cache_key: &str, cache_version: &str) -> PathBuf {
+		return self.directory.join(format!("{}-{}.{}", cache_key, cache_version, DOWNLOAD_CACHE_DATA_EXTENSION));
+	}
+
+	/// Opens the cached copy of the file, if it is up to date. Marks it as recently used
+	pub(crate) async fn open_cached_file(&self, storage_zone_name: &str, file: &File) -> Result<Option<fs::File>, Error> {
+		let (cache_key, cache_version) = self.get_cache_key_and_version(storage_zone_name, file)?;
+		let cache_filepath = self.get_cache_filepath(&cache_key, &cache_version);
+		let cached_file = match fs::File::open(&cache_filepath).await {
+			Ok(cached_file) => cached_file,
+			Err(open_error) if open_error.kind() == ErrorKind::NotFound => return Ok(None),
+			Err(open_error) => return Err(map_cache_io_error(open_error)),
+		};
+		let cached_file_metadata = cached_file.metadata().await.map_err(map_cache_io_error)?;
+		if cached_file_metadata.len() != file.length() {
+			return Ok(None);
+		}
+		// The modification time tracks the last use for the eviction
+		let cached_std_file = cached_file.into_std().await;
+		let cached_std_file = tokio::task::spawn_blocking(move || {
+			_ = cached_std_file.set_modified(SystemTime::now());
+			return cached_std_file;
+		}).await.map_err(|join_error| Error::new_from_message(&format!("Failed Accessing Download Cache - Error: {}", join_error)))?;
+		return Ok(Some(fs::File::from_std(cached_std_file)));
+	}
+
+	/// Writes the contents of the file into the cache, verifying the length and checksum
+	/// reported by Bunnystorage. Returns the cached copy opened for reading
+	pub(crate) async fn store_file<S, E>(&self, storage_zone_name: &str, file: &File, mut content_stream: S) -> Result<fs::File, Error>
+	where
+		S: Stream<Item = Result<Bytes, E>> + Unpin,
+		E: std::fmt::Display,
+	{
+		let (cache_key, cache_version) = self.get_cache_key_and_version(storage_zone_name, file)?;
+		fs::create_dir_all(&self.directory).await.map_err(map_cache_io_error)?;
+		let temp_filepath = self.directory.join(format!(".{}-{}.{}.{}", cache_key, cache_version, uuid::Uuid::new_v4(), DOWNLOAD_CACHE_TEMP_EXTENSION));
+		let store_result = self.write_temp_file(&temp_filepath, file, &mut content_stream).await;
+		if let Err(store_error) = store_result {
+			_ = fs::remove_file(&temp_filepath).await;
+			return Err(store_error);
+		}
+		let cache_filepath = self.get_cache_filepath(&cache_key, &cache_version);
+		fs::rename(&temp_filepath, &cache_filepath).await.map_err(map_cache_io_error)?;
+		// Opened before evicting, so the contents remain readable even if evicted right away
+		let cached_file = fs::File::open(&cache_filepath).await.map_err(map_cache_io_error)?;
+		self.remove_stale_versions(&cache_key, &cache_filepath).await?;
+		self.evict().await?;
+		return Ok(cached_file);
+	}
+
+	async fn write_temp_file<S, E>(&self, temp_filepath: &Path, file: &File, content_stream: &mut S) -> Result<(), Error>
+	where
+		S: Stream<Item = Result<Bytes, E>> + Unpin,
+		E: std::fmt::Display,
+	{
+		let temp_file = fs::File::create(temp_filepath).await.map_err(map_cache_io_error)?;
+		let mut temp_file_writer = BufWriter::new(temp_file);
+		let mut content_hasher = Sha256::new();
+		let mut content_length: u64 = 0;
+		while let Some(content_item_result) = content_stream.next().await {
+			let content_item = content_item_result
+				.map_err(|stream_error| Error::new_from_message(&format!("Failed Stream Contents - Error: {}", stream_error)))?;
+			content_hasher.update(&content_item);
+			content_length += content_item.len() as u64;
+			temp_file_writer.write_all(&content_item).await.map_err(map_cache_io_error)?;
+		}
+		temp_file_writer.flush().await.map_err(map_cache_io_error)?;
+		temp_file_writer.get_mut().sync_all().await.map_err(map_cache_io_error)?;
+		if content_length != file.length() {
+			return Err(Error::new_from_message(&format!("Failed Caching File - Expected {} bytes, received {}", file.length(), content_length)));
+		}
+		if let Some(expected_checksum) = file.parsed_checksum() {
+			let content_checksum = FileChecksum::Sha256(content_hasher.finalize().into());
+			if content_checksum != expected_checksum {
+				return Err(Error::new_from_message(&format!("Failed Caching File - Checksum mismatch. Expected {}, received {}", expected_checksum, content_checksum)));
+			}
+		}
+		return Ok(());
+	}
+
+	async fn remove_stale_versions(&self, cache_key: &str, cache_filepath: &Path) -> Result<(), Error> {
+		let cache_key_prefix = format!("{}-", cache_key);
+		let mut cache_entries = fs::read_dir(&self.directory).await.map_err(map_cache_io_error)?;
+		while let Some(cache_entry) = cache_entries.next_entry().await.map_err(map_cache_io_error)? {
+			let cache_entry_path = cache_entry.path();
+			let is_stale_version = cache_entry.file_name().to_string_lossy().starts_with(&cache_key_prefix)
+				&& cache_entry_path != cache_filepath;
+			if is_stale_version {
+				_ = fs::remove_file(&cache_entry_path).await;
+			}
+		}
+		return Ok(());
+	}
+
+	/// Removes the least recently used files until the cache is within max_bytes.
+	/// If another process is already evicting, this does nothing
+	pub async fn evict(&self) -> Result<(), Error> {
+		let lock_file = match fs::File::create(self.directory.join(DOWNLOAD_CACHE_LOCK_FILENAME)).await {
+			Ok(lock_file) => lock_file.into_std().await,
+			Err(lock_error) if lock_error.kind() == ErrorKind::NotFound => return Ok(()),
+			Err(lock_error) => return Err(map_cache_io_error(lock_error)),
+		};
+		// Does not wait for the lock, so it does not block the runtime. The lock is held until lock_file is dropped
+		if lock_file.try_lock().is_err() {
+			return Ok(());
+		}
+		let mut cached_files: Vec<(PathBuf, u64, SystemTime)> = Vec::new();
+		let mut cache_entries = fs::read_dir(&self.directory).await.map_err(map_cache_io_error)?;
+		while let Some(cache_entry) = cache_entries.next_entry().await.map_err(map_cache_io_error)? {
+			let cache_entry_path = cache_entry.path();
+			let Ok(cache_entry_metadata) = cache_entry.metadata().await else {
+				continue;
+			};
+			let cache_entry_modified = cache_entry_metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+			match cache_entry_path.extension().and_then(|extension| extension.to_str()) {
+				Some(DOWNLOAD_CACHE_DATA_EXTENSION) => cached_files.push((cache_entry_path, cache_entry_metadata.len(), cache_entry_modified)),
+				Some(DOWNLOAD_CACHE_TEMP_EXTENSION) => {
+					let is_stale_temp = cache_entry_modified.elapsed().is_ok_and(|temp_age| temp_age > DOWNLOAD_CACHE_STALE_TEMP_AGE);
+					if is_stale_temp {
+						_ = fs::remove_file(&cache_entry_path).await;
+					}
+				},
+				_ => {},
+			}
+		}
+		let mut cache_size: u64 = cached_files.iter().map(|(_, cached_file_size, _)| cached_file_size).sum();
+		cached_files.sort_by_key(|(_, _, cached_file_modified)| *cached_file_modified);
+		for (cached_filepath, cached_file_size, _) in cached_files.iter() {
+			if cache_size <= self.max_bytes {
+				break;
+			}
+			if fs::remove_file(cached_filepath).await.is_ok() {
+				cache_size -= cached_file_size;
+			}
+		}
+		return Ok(());
+	}
+
+	/// Removes every cached file
+	pub async fn clear(&self) -> Result<(), Error> {
+		let mut cache_entries = match fs::read_dir(&self.directory).await {
+			Ok(cache_entries) => cache_entries,
+			Err(read_dir_error) if read_dir_error.kind() == ErrorKind::NotFound => return Ok(()),
+			Err(read_dir_error) => return Err(map_cache_io_error(read_dir_error)),
+		};
+		while let Some(cache_entry) = cache_entries.next_entry().await.map_err(map_cache_io_error)? {
+			let cache_entry_path = cache_entry.path();
+			if cache_entry_path.extension().is_some_and(|extension| extension == DOWNLOAD_CACHE_DATA_EXTENSION) {
+				fs::remove_file(&cache_entry_path).await.map_err(map_cache_io_error)?;
+			}
+		}
+		return Ok(());
+	}
+}
+
+impl BunnyCDNClient {
+
+	/// Places a cache on the local disk in front of download_file and download_file_content.
+	/// With a cache every download first lists the parent directory to revalidate the cached copy.
+	/// Passing None disables the cache
+	pub fn set_download_cache(&mut self, download_cache: Option<DownloadCache>) {
+		self.download_cache = download_cache;
+	}
+
+	pub fn download_cache(&self) -> Option<&DownloadCache> {
+		return self.download_cache.as_ref();
+	}
+
+	/*
+		Retrieves the contents of a file through the download cache if one is set, otherwise
+		directly from Bunnystorage. Behaves like handle_get_and_stream_file_contents, so if a file
+		is present the content is written into it and the returned vector is empty.
+		Parameters:
+			remote_filepath: The filepath on bunnystorage relative to the root
+			file: file opened in another function, allows for streaming content into the file
+	*/
+	pub(crate) async fn handle_get_file_contents(&self, remote_filepath: &RemotePath, file: Option<&mut BufWriter<fs::File>>) -> Result<Vec<u8>, Error> {
+		let Some(download_cache) = &self.download_cache else {
+			return self.handle_get_and_stream_file_contents(remote_filepath, file).await;
+		};
+		let remote_file = match self.find_remote_entry(remote_filepath).await? {
+			Some(remote_file) if !remote_file.is_directory() => remote_file,
+			_ => return Err(Error::new_from_message(&format!("File not found - {}", remote_filepath))),
+		};
+		let storage_zone_name = self.storage_zone_name();
+		let mut cached_file = match download_cache.open_cached_file(storage_zone_name, &remote_file).await? {
+			Some(cached_file) => cached_file,
+			None => {
+				let download_file_response = self.open_remote_file_response(remote_filepath).await?;
+				download_cache.store_file(storage_zone_name, &remote_file, download_file_response.bytes_stream()).await?
+			},
+		};
+		let mut file_contents: Vec<u8> = Vec::new();
+		match file {
+			Some(file_pointer) => {
+				tokio::io::copy(&mut cached_file, file_pointer).await
+					.map_err(|write_file_error| Error::new_from_message(&format!("Failed Write File Contents - Error: {}", write_file_error)))?;
+			},
+			None => {
+				cached_file.read_to_end(&mut file_contents).await.map_err(map_cache_io_error)?;
+			},
+		}
+		return Ok(file_contents);
+	}
+
+}
+
+#[cfg(test)]
+mod download_cache_tests {
+	use crate::models::file::TestFileBuilder;
+	use super::*;
+
+	fn new_test_stream(content: &'static [u8]) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Unpin {
+		return futures::stream::iter(vec![Ok(Bytes::from_static(content))]);
+	}
+
+	#[tokio::test]
+	async fn test_download_cache() {
+		let test_directory = std::env::temp_dir().join(format!("bunnystorage-cache-{}", uuid::Uuid::new_v4()));
+		let download_cache = DownloadCache::new(&test_directory, 8);
+		// SHA256 of "test"
+		let test_file = TestFileBuilder::new("templates/a.txt").length(4).checksum("9F86D081884C7D659A2FEAA0C55AD015A3BF4F1B2B0B822CD15D6C15B0F00A08").build();
+		assert!(download_cache.open_cached_file("myzone", &test_file).await.is_ok_and(|cached_file| cached_file.is_none()));
+		assert!(download_cache.store_file("myzone", &test_file, new_test_stream(b"test")).await.is_ok());
+		assert!(download_cache.open_cached_file("myzone", &test_file).await.is_ok_and(|cached_file| cached_file.is_some()));
+		// A checksum mismatch is never cached
+		let corrupt_file = TestFileBuilder::new("templates/b.txt").length(4).checksum("9F86D081884C7D659A2FEAA0C55AD015A3BF4F1B2B0B822CD15D6C15B0F00A08").build();
+		assert!(download_cache.store_file("myzone", &corrupt_file, new_test_stream(b"tset")).await.is_err());
+		assert!(download_cache.open_cached_file("myzone", &corrupt_file).await.is_ok_and(|cached_file| cached_file.is_none()));
+		// Exceeding max_bytes evicts the least recently used file
+		let other_file = TestFileBuilder::new("templates/c.txt").length(8).checksum("").build();
+		tokio::time::sleep(Duration::from_millis(20)).await;
+		assert!(download_cache.store_file("myzone", &other_file, new_test_stream(b"abcdefgh")).await.is_ok());
+		assert!(download_cache.open_cached_file("myzone", &test_file).await.is_ok_and(|cached_file| cached_file.is_none()));
+		assert!(download_cache.open_cached_file("myzone", &other_file).await.is_ok_and(|cached_file| cached_file.is_some()));
+		assert!(download_cache.clear().await.is_ok());
+		assert!(download_cache.open_cached_file("myzone", &other_file).await.is_ok_and(|cached_file| cached_file.is_none()));
+		_ = fs::remove_dir_all(&test_directory).await;
+	}
+}
@@ -0,0 +1,231 @@
+use std::{sync::Mutex, time::{Duration, Instant}};
+
+use futures::future::join_all;
+
+use crate::models::storageendpoint::StorageEndpoint;
+
+use super::BunnyCDNClient;
+
+const DEFAULT_REEVALUATE_INTERVAL: Duration = Duration::from_secs(600);
+const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct EndpointSelectionParameters {
+	// The endpoints to choose between. Only endpoints of regions the storage zone is replicated to can serve
+	// its files. If empty, then the configured endpoint and the replication regions of the storage zone are used
+	pub candidates: Vec<StorageEndpoint>,
+	// How long the selected endpoint is used before the candidates are probed again
+	pub reevaluate_interval: Duration,
+	// Candidates which do not respond within the timeout are not selected
+	pub probe_timeout: Duration,
+}
+
+impl Default for EndpointSelectionParameters {
+
+	fn default() -> Self {
+		return EndpointSelectionParameters{
+			candidates: Vec::new(),
+			reevaluate_interval: DEFAULT_REEVALUATE_INTERVAL,
+			probe_timeout: DEFAULT_PROBE_TIMEOUT,
+		};
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct EndpointLatency {
+	pub endpoint: StorageEndpoint,
+	// The round trip time of the probe. None if the endpoint could not be reached
+	pub latency: Option<Duration>,
+}
+
+struct EndpointSelection {
+	endpoint: StorageEndpoint,
+	selected_at: Instant,
+}
+
+pub(crate) struct EndpointSelector {
+	params: EndpointSelectionParameters,
+	// The endpoints of the storage zone, which are looked up once when no candidates are provided
+	zone_endpoints: Mutex<Option<Vec<StorageEndpoint>>>,
+	selection: Mutex<Option<EndpointSelection>>,
+}
+
+impl EndpointSelector {
+
+	fn current(&self) -> Option<StorageEndpoint> {
+		let selection = self.selection.lock().ok()?;
+		return selection.as_ref()
+			.filter(|selection| selection.selected_at.elapsed() < self.params.reevaluate_interval)
+			.map(|selection| selection.endpoint);
+	}
+
+	fn select(&self, endpoint: StorageEndpoint) {
+		if let Ok(mut selection) = self.selection.lock() {
+			*selection = Some(EndpointSelection{
+				endpoint,
+				selected_at: Instant::now(),
+			});
+		}
+	}
+}
+
+/// The reachable endpoint with the lowest latency
+pub(crate) fn select_fastest_endpoint(endpoint_latencies: &[EndpointLatency]) -> Option<StorageEndpoint> {
+	return endpoint_latencies.iter()
+		.filter_map(|endpoint_latency| endpoint_latency.latency.map(|latency| (endpoint_latency.endpoint, latency)))
+		.min_by_key(|(_, latency)| *latency)
+		.map(|(endpoint, _)| endpoint);
+}
+
+/// The configured endpoint followed by the other endpoints of the storage zone, without duplicates
+pub(crate) fn merge_zone_endpoints(configured_endpoint: StorageEndpoint, zone_endpoints: &[StorageEndpoint]) -> Vec<StorageEndpoint> {
+	let mut merged_endpoints: Vec<StorageEndpoint> = vec![configured_endpoint];
+	for zone_endpoint in zone_endpoints.iter() {
+		if !merged_endpoints.contains(zone_endpoint) {
+			merged_endpoints.push(*zone_endpoint);
+		}
+	}
+	return merged_endpoints;
+}
+
+impl BunnyCDNClient {
+
+	/// Reads files and directory listings from the candidate endpoint with the lowest latency,
+	/// while uploads and deletes keep using the configured endpoint. The candidates are probed
+	/// on the first read and again once the selection is older than the reevaluate interval.
+	/// Passing None reads from the configured endpoint again.
+	///
+	/// Replicas may lag behind the configured endpoint, so reading a file right after writing it
+	/// can return the previous version. See wait_for_replication. Internal checks such as copy
+	/// verification, version backups and duplicate verification always use the configured endpoint
+	pub fn set_endpoint_selection(&mut self, params: Option<EndpointSelectionParameters>) {
+		self.endpoint_selector = params.map(|params| EndpointSelector{
+			params,
+			zone_endpoints: Mutex::new(None),
+			selection: Mutex::new(None),
+		});
+	}
+
+	/// The endpoint currently selected for reading. None if endpoint selection is disabled
+	/// or no endpoint has been selected yet
+	pub fn selected_read_endpoint(&self) -> Option<StorageEndpoint> {
+		return self.endpoint_selector.as_ref().and_then(|endpoint_selector| endpoint_selector.current());
+	}
+
+	/// Measures the round trip time to each endpoint. The endpoints are probed concurrently
+	///
+	/// Parameters:
+	/// * endpoints: The endpoints to probe
+	/// * timeout: Endpoints which do not respond within the timeout are reported as unreachable
+	pub async fn probe_endpoints(&self, endpoints: &[StorageEndpoint], timeout: Duration) -> Vec<EndpointLatency> {
+		let probes = endpoints.iter().map(|endpoint| async move {
+			let probe_started_at = Instant::now();
+			// Any response counts, since only the round trip time is of interest
+			let probe_result = self.http_client.head(endpoint.url())
+				.timeout(timeout)
+				.send()
+				.await;
+			return EndpointLatency{
+				endpoint: *endpoint,
+				latency: probe_result.ok().map(|_| probe_started_at.elapsed()),
+			};
+		});
+		return join_all(probes).await;
+	}
+
+	/*
+		The configured endpoint followed by the endpoints of the replication regions of the storage zone.
+		If the storage zone cannot be looked up, e.g. without an API key, then only the configured
+		endpoint is used until the next reevaluation.
+		Parameters:
+			endpoint_selector: Caches the endpoints once looked up
+	*/
+	async fn get_zone_endpoints(&self, endpoint_selector: &EndpointSelector) -> Vec<StorageEndpoint> {
+		if let Some(zone_endpoints) = endpoint_selector.zone_endpoints.lock().ok().and_then(|zone_endpoints| zone_endpoints.clone()) {
+			return zone_endpoints;
+		}
+		let storage_zone_result = self.attempt_find_storage_zone(&self.config.storage_zone_name, Some(false)).await;
+		let Ok(Some(storage_zone)) = storage_zone_result else {
+			return vec![self.config.endpoint];
+		};
+		let zone_endpoints = merge_zone_endpoints(self.config.endpoint, &storage_zone.endpoints());
+		if let Ok(mut cached_zone_endpoints) = endpoint_selector.zone_endpoints.lock() {
+			*cached_zone_endpoints = Some(zone_endpoints.clone());
+		}
+		return zone_endpoints;
+	}
+
+	/// Probes the candidates and selects the fastest for reading, regardless of the age of the
+	/// current selection. Falls back to the configured endpoint if no candidate is reachable.
+	/// Returns the selected endpoint
+	pub async fn reevaluate_read_endpoint(&self) -> StorageEndpoint {
+		let Some(endpoint_selector) = &self.endpoint_selector else {
+			return self.config.endpoint;
+		};
+		let candidates = match endpoint_selector.params.candidates.is_empty() {
+			true => self.get_zone_endpoints(endpoint_selector).await,
+			false => endpoint_selector.params.candidates.clone(),
+		};
+		let endpoint_latencies = self.probe_endpoints(&candidates, endpoint_selector.params.probe_timeout).await;
+		let selected_endpoint = select_fastest_endpoint(&endpoint_latencies).unwrap_or(self.config.endpoint);
+		endpoint_selector.select(selected_endpoint);
+		return selected_endpoint;
+	}
+
+	pub(crate) async fn read_endpoint(&self) -> StorageEndpoint {
+		let Some(endpoint_selector) = &self.endpoint_selector else {
+			return self.config.endpoint;
+		};
+		if let Some(selected_endpoint) = endpoint_selector.current() {
+			return selected_endpoint;
+		}
+		return self.reevaluate_read_endpoint().await;
+	}
+
+}
+
+#[cfg(test)]
+mod endpoint_selection_tests {
+	use crate::{client::BunnyCDNClientConfig, errors::Error};
+	use super::*;
+
+	#[test]
+	fn test_select_fastest_endpoint() {
+		let endpoint_latencies = vec![
+			EndpointLatency{ endpoint: StorageEndpoint::Falkenstein, latency: Some(Duration::from_millis(120)) },
+			EndpointLatency{ endpoint: StorageEndpoint::NewYork, latency: None },
+			EndpointLatency{ endpoint: StorageEndpoint::London, latency: Some(Duration::from_millis(15)) },
+		];
+		assert_eq!(select_fastest_endpoint(&endpoint_latencies), Some(StorageEndpoint::London));
+		assert_eq!(select_fastest_endpoint(&endpoint_latencies[1..2]), None);
+	}
+
+	#[test]
+	fn test_merge_zone_endpoints() {
+		let zone_endpoints = vec![StorageEndpoint::NewYork, StorageEndpoint::Falkenstein, StorageEndpoint::Sydney];
+		assert_eq!(merge_zone_endpoints(StorageEndpoint::Falkenstein, &zone_endpoints), vec![StorageEndpoint::Falkenstein, StorageEndpoint::NewYork, StorageEndpoint::Sydney]);
+		assert_eq!(merge_zone_endpoints(StorageEndpoint::London, &[]), vec![StorageEndpoint::London]);
+	}
+
+	#[tokio::test]
+	async fn test_read_endpoint() {
+		let mut client = BunnyCDNClient::new(BunnyCDNClientConfig::new_offline()).unwrap();
+		assert_eq!(client.read_endpoint().await, StorageEndpoint::Falkenstein);
+		assert!(client.selected_read_endpoint().is_none());
+		client.set_endpoint_selection(Some(EndpointSelectionParameters::default()));
+		client.endpoint_selector.as_ref().unwrap().select(StorageEndpoint::Sydney);
+		assert_eq!(client.read_endpoint().await, StorageEndpoint::Sydney);
+		assert_eq!(client.selected_read_endpoint(), Some(StorageEndpoint::Sydney));
+		// Writes keep using the configured endpoint
+		assert!(client.get_remote_url(&crate::remotepath::RemotePath::file("a.txt").unwrap()).starts_with("https://storage.bunnycdn.com/"));
+	}
+
+	#[tokio::test]
+	async fn test_reevaluate_read_endpoint() {
+		let client_result: Result<BunnyCDNClient, Error> = BunnyCDNClient::new_from_env();
+		assert!(client_result.is_ok());
+		let mut client = client_result.unwrap();
+		client.set_endpoint_selection(Some(EndpointSelectionParameters::default()));
+		let selected_endpoint = client.reevaluate_read_endpoint().await;
+		assert_eq!(client.selected_read_endpoint(), Some(selected_endpoint));
+	}
+}
@@ -0,0 +1,174 @@
+use std::{future::Future, io, pin::Pin, task::{ready, Context, Poll}};
+
+use bytes::Bytes;
+use futures::{channel::mpsc, stream::BoxStream, StreamExt, TryStreamExt};
+use reqwest::Body;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio_util::io::StreamReader;
+
+use crate::{errors::Error, remotepath::RemotePath};
+
+use super::BunnyCDNClient;
+
+// The number of written chunks which may be buffered while waiting for the upload to consume them
+const REMOTE_FILE_WRITER_BUFFER_SIZE: usize = 8;
+
+fn map_remote_io_error(error: Error) -> io::Error {
+	return io::Error::other(error);
+}
+
+/// Reads the contents of a remote file as they are downloaded. Created by BunnyCDNClient::open_read
+pub struct RemoteFileReader {
+	stream_reader: StreamReader<BoxStream<'static, io::Result<Bytes>>, Bytes>,
+}
+
+impl AsyncRead for RemoteFileReader {
+
+	fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+		return Pin::new(&mut self.get_mut().stream_reader).poll_read(cx, buf);
+	}
+}
+
+type RemoteUploadFuture<'a> = Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>>;
+
+/// Uploads everything written to it into a remote file. Created by BunnyCDNClient::open_write
+///
+/// The upload is only complete once shutdown has returned successfully, which is also where
+/// any upload error is reported. Dropping the writer before shutdown aborts the upload.
+pub struct RemoteFileWriter<'a> {
+	sender: Option<mpsc::Sender<io::Result<Bytes>>>,
+	upload_future: Option<RemoteUploadFuture<'a>>,
+	upload_failed: bool,
+}
+
+impl RemoteFileWriter<'_> {
+
+	// Drives the upload request. Ready once the upload has finished, successfully or not
+	fn poll_upload(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		let Some(upload_future) = self.upload_future.as_mut() else {
+			if self.upload_failed {
+				return Poll::Ready(Err(io::Error::other("The upload of the remote file has failed")));
+			}
+			return Poll::Ready(Ok(()));
+		};
+		let upload_result = ready!(upload_future.as_mut().poll(cx));
+		self.upload_future = None;
+		self.upload_failed = upload_result.is_err();
+		return Poll::Ready(upload_result.map_err(map_remote_io_error));
+	}
+}
+
+impl AsyncWrite for RemoteFileWriter<'_> {
+
+	fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+		let remote_file_writer = self.get_mut();
+		if let Poll::Ready(upload_result) = remote_file_writer.poll_upload(cx) {
+			upload_result?;
+			return Poll::Ready(Err(io::Error::new(io::ErrorKind::BrokenPipe, "The upload of the remote file has already finished")));
+		}
+		let Some(sender) = remote_file_writer.sender.as_mut() else {
+			return Poll::Ready(Err(io::Error::new(io::ErrorKind::BrokenPipe, "The remote file writer has been shut down")));
+		};
+		if ready!(sender.poll_ready(cx)).is_err() {
+			return Poll::Ready(Err(io::Error::new(io::ErrorKind::BrokenPipe, "The upload of the remote file has stopped consuming data")));
+		}
+		sender.start_send(Ok(Bytes::copy_from_slice(buf)))
+			.map_err(|send_error| io::Error::new(io::ErrorKind::BrokenPipe, send_error))?;
+		return Poll::Ready(Ok(buf.len()));
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		let remote_file_writer = self.get_mut();
+		if let Poll::Ready(Err(upload_error)) = remote_file_writer.poll_upload(cx) {
+			return Poll::Ready(Err(upload_error));
+		}
+		return Poll::Ready(Ok(()));
+	}
+
+	fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		let remote_file_writer = self.get_mut();
+		// Closing the channel ends the request body
+		remote_file_writer.sender = None;
+		return remote_file_writer.poll_upload(cx);
+	}
+}
+
+impl BunnyCDNClient {
+
+	/// Opens a remote file for reading, so it can be handed to anything expecting a tokio AsyncRead,
+	/// e.g. decompressors or hashers. The contents are read while they are downloaded.
+	///
+	/// Parameters:
+	/// * remote_filepath: The filepath on bunnystorage relative to the root
+	pub async fn open_read(&self, remote_filepath: &str) -> Result<RemoteFileReader, Error> {
+		let used_remote_filepath = RemotePath::file(remote_filepath)?;
+		let download_file_response = self.open_remote_file_response(&used_remote_filepath).await?;
+		let download_file_stream = download_file_response.bytes_stream()
+			.map_err(|stream_error| io::Error::other(format!("Failed Stream Contents - Error: {}", stream_error)))
+			.boxed();
+		return Ok(RemoteFileReader{
+			stream_reader: StreamReader::new(download_file_stream),
+		});
+	}
+
+	/// Opens a remote file for writing. Everything written is streamed into a single upload,
+	/// which completes when the writer is shut down. The content type is guessed from the extension.
+	///
+	/// # Examples
+	/// ```no_run
+	/// use bunnystorage_rs::client::BunnyCDNClient;
+	/// use tokio::io::AsyncWriteExt;
+	/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+	/// let client = BunnyCDNClient::new_from_env()?;
+	/// let mut remote_file_writer = client.open_write("reports/summary.csv")?;
+	/// remote_file_writer.write_all(b"a,b\n1,2\n").await?;
+	/// remote_file_writer.shutdown().await?;
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Parameters:
+	/// * remote_filepath: The filepath on bunnystorage relative to the root
+	pub fn open_write(&self, remote_filepath: &str) -> Result<RemoteFileWriter<'_>, Error> {
+		self.check_write_password_ok()?;
+		let used_remote_filepath = RemotePath::file(remote_filepath)?;
+		let (sender, receiver) = mpsc::channel::<io::Result<Bytes>>(REMOTE_FILE_WRITER_BUFFER_SIZE);
+		let upload_future = async move {
+			return self.put_remote_file(&used_remote_filepath, Body::wrap_stream(receiver), None).await;
+		};
+		return Ok(RemoteFileWriter{
+			sender: Some(sender),
+			upload_future: Some(Box::pin(upload_future)),
+			upload_failed: false,
+		});
+	}
+
+}
+
+#[cfg(test)]
+mod file_io_tests {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	use super::*;
+
+	#[tokio::test]
+	async fn test_open_write_and_read() {
+		let client_result: Result<BunnyCDNClient, Error> = BunnyCDNClient::new_from_env();
+		assert!(client_result.is_ok());
+		let client = client_result.unwrap();
+		let test_remote_filepath = "/tests/files/fileio/test.csv";
+		let test_writer_result = client.open_write(test_remote_filepath);
+		assert!(test_writer_result.is_ok());
+		let mut test_writer = test_writer_result.unwrap();
+		assert!(test_writer.write_all(b"a,b\n").await.is_ok());
+		assert!(test_writer.write_all(b"1,2\n").await.is_ok());
+		assert!(test_writer.shutdown().await.is_ok());
+		let test_reader_result = client.open_read(test_remote_filepath).await;
+		assert!(test_reader_result.is_ok());
+		let mut test_contents = String::new();
+		let test_read_result = test_reader_result.unwrap().read_to_string(&mut test_contents).await;
+		assert!(test_read_result.is_ok());
+		assert_eq!(test_contents, "a,b\n1,2\n");
+		assert!(client.delete_file(test_remote_filepath).await.is_ok());
+	}
+}
@@ -1,17 +1,37 @@
-use std::{collections::HashMap, ffi::OsStr, fs, io::{Read, Write}, path::{self, PathBuf}};
+use std::{collections::HashMap, ffi::OsStr, ops::Range, path::{self, PathBuf}};
+use chrono::{DateTime, Utc};
 use futures::StreamExt;
+use tokio::{fs, io::{AsyncWriteExt, BufWriter}};
+use tokio_util::io::ReaderStream;
 
+use reqwest::{header::{CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, LAST_MODIFIED, RANGE}, Body, StatusCode};
 use serde_json::Value;
 
-use crate::{errors::Error, models::file::File};
+use crate::{errors::Error, models::{file::File, storageendpoint::StorageEndpoint}, remotepath::RemotePath};
 
 use super::{BunnyCDNClient, BunnyCDNDataOptions, ContentType, ACCESS_KEY_HEADER_NAME, CONTENT_TYPE_HEADER_NAME};
 
 
+/// The information about a remote file which is available without listing its directory
+#[derive(Debug, Clone)]
+pub struct RemoteFileHead {
+	// The size of the file in bytes
+	pub length: u64,
+	// The content type reported by Bunnystorage, if any
+	pub content_type: Option<String>,
+	// When the file was last changed, if reported in the Last-Modified header
+	pub last_modified: Option<DateTime<Utc>>,
+}
+
 impl BunnyCDNClient {
 
-	fn get_files_root_url(&self) -> String {
-		let endpoint_url = self.config.endpoint.url();
+	pub(crate) fn get_files_root_url(&self) -> String {
+		return self.get_endpoint_files_root_url(&self.config.endpoint);
+	}
+
+	/// The root URL of the storage zone on another endpoint than the configured one
+	pub(crate) fn get_endpoint_files_root_url(&self, endpoint: &StorageEndpoint) -> String {
+		let endpoint_url = endpoint.url();
 		let files_root_url = format!(
 			"{}/{}",
 			endpoint_url,
@@ -20,23 +40,63 @@ impl BunnyCDNClient {
 		return files_root_url
 	}
 
-	/// This function add a trailing / to the directory provided in case there is
-	/// one missing. This is done to avoid having to do it manually every single time
+	/// The percent encoded URL of a file or directory in the storage zone
+	pub(crate) fn get_remote_url(&self, remote_path: &RemotePath) -> String {
+		return format!(
+			"{}/{}",
+			self.get_files_root_url(),
+			remote_path.to_url_path(),
+		);
+	}
+
+	/// The percent encoded URL of a file or directory on the endpoint used for reading,
+	/// which differs from get_remote_url when endpoint selection is enabled
+	pub(crate) async fn get_read_url(&self, remote_path: &RemotePath) -> String {
+		let read_endpoint = self.read_endpoint().await;
+		return format!(
+			"{}/{}",
+			self.get_endpoint_files_root_url(&read_endpoint),
+			remote_path.to_url_path(),
+		);
+	}
+
+	/// The provided path is always treated as a directory, so a missing trailing /
+	/// is added. This is done to avoid having to do it manually every single time
 	/// Unlike for delete directory then this has no consequences, therefore this will
 	/// not error out if the trailing / is missing.
 	/// Parameters:
 	/// * 	directory -> relative to the root directory of the storage name
-
+	///
 	/// See https://docs.bunny.net/reference/get_-storagezonename-path- for documentation
 	pub async fn get_files(&self, directory: &str) -> Result<Vec<File>, Error> {
-		let mut used_directory = directory.trim().to_string();
-		if used_directory.starts_with("/") {
-			used_directory.insert_str(0, "/");
+		let remote_directory = RemotePath::directory(directory)?;
+		return self.get_remote_directory_entries(&remote_directory).await;
+	}
+
+	pub(crate) async fn get_remote_directory_entries(&self, remote_directory: &RemotePath) -> Result<Vec<File>, Error> {
+		let Some(listing_cache) = &self.listing_cache else {
+			return self.fetch_remote_directory_entries(remote_directory).await;
+		};
+		if let Some(cached_files) = listing_cache.get(&remote_directory.as_directory()) {
+			return Ok(cached_files);
 		}
+		let fetched_generation = listing_cache.generation();
+		let files = self.fetch_remote_directory_entries(remote_directory).await?;
+		listing_cache.insert(remote_directory, &files, fetched_generation);
+		return Ok(files);
+	}
+
+	async fn fetch_remote_directory_entries(&self, remote_directory: &RemotePath) -> Result<Vec<File>, Error> {
+		let read_endpoint = self.read_endpoint().await;
+		return self.fetch_endpoint_directory_entries(&read_endpoint, remote_directory).await;
+	}
+
+	/// Lists a directory on the provided endpoint, bypassing the listing cache
+	pub(crate) async fn fetch_endpoint_directory_entries(&self, endpoint: &StorageEndpoint, remote_directory: &RemotePath) -> Result<Vec<File>, Error> {
 		let files_url = format!(
-			"{}/{}/",
-			self.get_files_root_url(),
-			used_directory
+			"{}/{}",
+			self.get_endpoint_files_root_url(endpoint),
+			remote_directory.as_directory().to_url_path(),
 		);
 		let files_response = self.get(
 			&files_url,
@@ -64,6 +124,71 @@ impl BunnyCDNClient {
 		return Ok(files);
 	}
 	
+	/// Retrieves every file below the provided directory by walking all of its
+	/// subdirectories. Only files are returned, directories are traversed but not included.
+	/// Parameters:
+	/// * 	directory -> relative to the root directory of the storage name
+	pub async fn get_files_recursive(&self, directory: &str) -> Result<Vec<File>, Error> {
+		return self.walk_files_recursive(&RemotePath::directory(directory)?, false).await;
+	}
+
+	/// Retrieves every file below the provided directory from the configured endpoint without the cache.
+	/// Used before deleting, since replicas and the cache may not have seen the latest writes yet
+	pub(crate) async fn get_primary_files_recursive(&self, remote_directory: &RemotePath) -> Result<Vec<File>, Error> {
+		return self.walk_files_recursive(remote_directory, true).await;
+	}
+
+	async fn walk_files_recursive(&self, remote_directory: &RemotePath, primary_only: bool) -> Result<Vec<File>, Error> {
+		let mut files: Vec<File> = Vec::new();
+		let mut pending_directories: Vec<RemotePath> = vec![remote_directory.as_directory()];
+		while let Some(pending_directory) = pending_directories.pop() {
+			let directory_entries = match primary_only {
+				true => self.fetch_endpoint_directory_entries(&self.config.endpoint, &pending_directory).await?,
+				false => self.get_remote_directory_entries(&pending_directory).await?,
+			};
+			for directory_entry in directory_entries.into_iter() {
+				if directory_entry.is_directory() {
+					pending_directories.push(directory_entry.to_remote_path()?);
+				} else {
+					files.push(directory_entry);
+				}
+			}
+		}
+		return Ok(files);
+	}
+
+	/// Looks up a single entry by listing its parent directory.
+	/// Returns None if the parent does not contain an entry with the same name
+	pub(crate) async fn find_remote_entry(&self, remote_path: &RemotePath) -> Result<Option<File>, Error> {
+		let (parent_directory, entry_name) = match (remote_path.parent(), remote_path.file_name()) {
+			(Some(parent_directory), Some(entry_name)) => (parent_directory, entry_name),
+			_ => return Ok(None),
+		};
+		let parent_entries = self.get_remote_directory_entries(&parent_directory).await?;
+		for parent_entry in parent_entries.into_iter() {
+			if parent_entry.object_name() == entry_name {
+				return Ok(Some(parent_entry));
+			}
+		}
+		return Ok(None);
+	}
+
+	/// Looks up a single entry by listing its parent directory on the configured endpoint without the cache.
+	/// Used for checks that must see a preceding write, which replicas and the cache may not have yet
+	pub(crate) async fn find_primary_entry(&self, remote_path: &RemotePath) -> Result<Option<File>, Error> {
+		let (parent_directory, entry_name) = match (remote_path.parent(), remote_path.file_name()) {
+			(Some(parent_directory), Some(entry_name)) => (parent_directory, entry_name),
+			_ => return Ok(None),
+		};
+		let parent_entries = self.fetch_endpoint_directory_entries(&self.config.endpoint, &parent_directory).await?;
+		for parent_entry in parent_entries.into_iter() {
+			if parent_entry.object_name() == entry_name {
+				return Ok(Some(parent_entry));
+			}
+		}
+		return Ok(None);
+	}
+
 	fn validate_filepath(&self, filepath: &str) -> Result<String, Error> {
 		let trimmed_filepath = filepath.trim().to_string();
 		if trimmed_filepath.is_empty() {
@@ -90,11 +215,12 @@ impl BunnyCDNClient {
 		}
 	}
 
-	fn validate_local_filepath(&self, filepath: &str, require_file_exist: bool) -> Result<String, Error> {
+	async fn validate_local_filepath(&self, filepath: &str, require_file_exist: bool) -> Result<String, Error> {
 		let validated_filepath = self.validate_filepath(filepath)?;
 		let absolute_filepath_buffer = self.attempt_make_filepath_absolute(&validated_filepath)?;
 		if require_file_exist {
-			let file_exists = fs::exists(&absolute_filepath_buffer)
+			let file_exists = fs::try_exists(&absolute_filepath_buffer)
+				.await
 				.map_err(|file_exists_error| Error::new_from_message(&file_exists_error.to_string()))?;
 	
 			if !file_exists {
@@ -128,119 +254,198 @@ impl BunnyCDNClient {
 		}
 	}
 
-	fn prepare_remote_filepath(&self, filepath: &str) -> Result<String, Error> {
-		let mut validated_filepath: String = self.validate_filepath(filepath)?;
-		if validated_filepath.starts_with(".") {
-			validated_filepath.remove(0);
-		}
-		if validated_filepath.starts_with("/") {
-			validated_filepath.remove(0);
-		}
-		return Ok(validated_filepath);
-	}
-
-	fn derive_filepath_common(&self, source_filepath: &str, target_filepath: &str) -> Result<String, Error> {
-		let validated_source_filepath = self.validate_filepath(source_filepath)?;
-		let trimmed_target_filepath = target_filepath.trim();
-		if trimmed_target_filepath.is_empty() {
-			return Ok(validated_source_filepath);
+	/*
+		A trailing / on the remote filepath marks it as a directory, in which case the filename
+		of the local file is appended to it. Otherwise the remote filepath is the target itself
+		which allows for renaming the file, however the file extensions must match.
+	*/
+	fn evaluate_remote_target_filepath(&self, local_filepath: &str, remote_filepath: Option<&str>) -> Result<RemotePath, Error> {
+		let provided_remote_filepath = match remote_filepath {
+			None => return RemotePath::file(local_filepath),
+			Some(provided_remote_filepath) => provided_remote_filepath,
+		};
+		let remote_target = RemotePath::parse(provided_remote_filepath)?;
+		if remote_target.is_directory() {
+			let local_filename: String = self.retrieve_filepath_filename(local_filepath);
+			if local_filename.is_empty() {
+				return Err(Error::new_from_message(&format!("Invalid Local Filepath - Missing filename. Provided: {}", local_filepath)));
+			}
+			return remote_target.join(&local_filename);
 		}
 		// Check if there is an extension present on both and if they are equal
-		let local_file_extension: String = self.retrieve_filepath_extension(&validated_source_filepath);
-		let remote_file_extension: String = self.retrieve_filepath_extension(trimmed_target_filepath);
+		let local_file_extension: String = self.retrieve_filepath_extension(local_filepath);
+		let remote_file_extension: &str = remote_target.extension().unwrap_or("");
 		if !local_file_extension.is_empty() && !remote_file_extension.is_empty() && local_file_extension != remote_file_extension {
 			return Err(Error::new_from_message(&format!("Invalid Remote File Extension - Expected {}, Received {}", local_file_extension, remote_file_extension)));
 		}
-		// If there is a local file extension but no file extension on the remote filepath,
-		// then it is assumed to be a directory and the filename from the original file
-		// will be appended to the directory
-		let mut derived_remote_path: String = target_filepath.to_owned();
-		let local_filename: String = self.retrieve_filepath_filename(&validated_source_filepath);
-		if !local_filename.is_empty() && remote_file_extension.is_empty() {
-			derived_remote_path = format!("{}/{}", target_filepath, local_filename);
-		}
-		return Ok(derived_remote_path);
-	}
-
-	fn derive_remote_filepath(&self, local_filepath: &str, remote_filepath: &str) -> Result<String, Error> {
-		let derived_remote_path: String = self.derive_filepath_common(local_filepath, remote_filepath)?;
-		return self.prepare_remote_filepath(&derived_remote_path);
-	}
-
-	fn evaluate_remote_target_filepath(&self, local_filepath: &str, remote_filepath: Option<&str>) -> Result<String, Error> {
-		return match remote_filepath {
-			None => self.prepare_remote_filepath(local_filepath),
-			Some(provided_remote_filepath) => self.derive_remote_filepath(local_filepath, provided_remote_filepath),
-		};
+		return Ok(remote_target);
 	}
 
 	///	If the target is not provided, then the source filepath will be used.
 	///	For example say that a file is stored on /my/test/directory/file.json, then
 	///	the target, if not provided, will be uploaded to that in Bunny Storage.
 	///	However is a target is provided, then this will be used instead.
-	///	The target must be filepath to allow for renaming a file and the
-	///	file extensions must match.
+	///	The target may be a filepath to allow for renaming a file, in which case the
+	///	file extensions must match, or a directory with a trailing / to keep the filename.
 	/// 
 	/// Parameters:
 	/// 	local_filepath: absolute filepath to a local file
 	/// 	remote_filepath: If provided, absolute filepath to a path on Bunnystorage
-
+	///
 	/// If the remote_filepath is not provided, then the local_filepath is used.
+	/// The content type is derived from the file extension, see guess_content_type.
 	/// See https://docs.bunny.net/reference/put_-storagezonename-path-filename for documentation
 	pub async fn upload_file(&self, local_filepath: &str, remote_filepath: Option<&str>) -> Result<(), Error> {
+		return self.upload_file_with_options(local_filepath, remote_filepath, None).await;
+	}
+
+	/// Same as upload_file, but allows for providing the content type and additional headers.
+	/// The content type is resolved in the following order:
+	/// 1. The content_type on the options
+	/// 2. A Content-Type header on the options
+	/// 3. The content type guessed from the extension of the remote filepath
+	///
+	/// Parameters:
+	/// 	local_filepath: absolute filepath to a local file
+	/// 	remote_filepath: If provided, absolute filepath to a path on Bunnystorage
+	/// 	options: The content type and additional headers to send along with the file
+	pub async fn upload_file_with_options(&self, local_filepath: &str, remote_filepath: Option<&str>, options: Option<&BunnyCDNDataOptions>) -> Result<(), Error> {
 		self.check_write_password_ok()?;
 		// Evaluate both target and source filepath
-		let used_local_filepath: String = self.validate_local_filepath(local_filepath, true)?;
-		let used_remote_filepath: String = self.evaluate_remote_target_filepath(local_filepath, remote_filepath)?;
-		// Read the file contents in a vector
-		let mut file_contents = Vec::new();
-		let mut file = fs::File::open(used_local_filepath)
+		let used_local_filepath: String = self.validate_local_filepath(local_filepath, true).await?;
+		let used_remote_filepath: RemotePath = self.evaluate_remote_target_filepath(local_filepath, remote_filepath)?;
+		// Stream the file contents instead of reading the entire file into memory
+		let file = fs::File::open(used_local_filepath)
+			.await
 			.map_err(|open_file_error| Error::new_from_message(&open_file_error.to_string()))?;
-		
-		let read_file_result = file.read_to_end(&mut file_contents);
-		if let Err(read_file_error) = read_file_result {
-			return Err(Error::new_from_message(&format!("Failed Read File - {}", read_file_error.to_string())));
-		}
-		let upload_file_url: String = format!(
-			"{}/{}",
-			self.get_files_root_url(),
-			used_remote_filepath,
-		);
-		let mut upload_file_headers = HashMap::<String, String>::new();
-		upload_file_headers.insert(CONTENT_TYPE_HEADER_NAME.to_string(), ContentType::ApplicationOctetStream.name().to_string());
+
+		let file_metadata = file.metadata()
+			.await
+			.map_err(|file_metadata_error| Error::new_from_message(&format!("Failed Read File - {}", file_metadata_error)))?;
+
+		// The length is known up front, so the upload does not have to be chunked
+		let mut upload_file_headers: HashMap<String, String> = options
+			.and_then(|provided_options| provided_options.headers.clone())
+			.unwrap_or_default();
+		// A Content-Length header on the options would be sent alongside the actual length
+		upload_file_headers.retain(|header_name, _| !header_name.eq_ignore_ascii_case(CONTENT_LENGTH.as_str()));
+		upload_file_headers.insert(CONTENT_LENGTH.to_string(), file_metadata.len().to_string());
 		let upload_file_options = BunnyCDNDataOptions{
 			headers: Some(upload_file_headers),
+			content_type: options.and_then(|provided_options| provided_options.content_type.clone()),
 		};
+		return self.put_remote_file(
+			&used_remote_filepath,
+			Body::wrap_stream(ReaderStream::new(file)),
+			Some(&upload_file_options),
+		).await;
+	}
+
+	/// Uploads content held in memory to a file on Bunnystorage.
+	/// The content type is resolved the same way as for upload_file_with_options.
+	///
+	/// Parameters:
+	/// 	remote_filepath: The filepath on Bunnystorage
+	/// 	content: The content of the file
+	/// 	options: The content type and additional headers to send along with the file
+	pub async fn upload_file_content(&self, remote_filepath: &str, content: Vec<u8>, options: Option<&BunnyCDNDataOptions>) -> Result<(), Error> {
+		let used_remote_filepath = RemotePath::file(remote_filepath)?;
+		return self.put_remote_file(&used_remote_filepath, content, options).await;
+	}
+
+	/*
+		Every upload ends up here, so this is the single place where the content type is
+		resolved and the write password is checked.
+		Parameters:
+			remote_filepath: The filepath on bunnystorage relative to the root
+			data: The content of the file, either in memory or as a stream
+			options: The content type and additional headers provided by the caller
+	*/
+	pub(crate) async fn put_remote_file<T: Into<Body>>(&self, remote_filepath: &RemotePath, data: T, options: Option<&BunnyCDNDataOptions>) -> Result<(), Error> {
+		self.check_write_password_ok()?;
+		if remote_filepath.is_directory() {
+			return Err(Error::new_from_message(&format!("Invalid Filepath - Provided: {}. Cannot upload to a directory", remote_filepath)));
+		}
+		// Boxed since backing up the existing version uploads through this function as well
+		Box::pin(self.backup_file_version(remote_filepath)).await?;
+		let upload_file_url: String = self.get_remote_url(remote_filepath);
+		let upload_file_options = self.prepare_upload_options(remote_filepath, options);
 		let write_password = self.config.write_password.clone().unwrap();
 		let upload_file_result = self.put(
 			&upload_file_url,
 			&write_password,
-			file_contents,
+			data,
 			Some(&upload_file_options),
 		).await;
-		return upload_file_result
+		self.invalidate_listing_cache(remote_filepath);
+		upload_file_result?;
+		return self.purge_changed_path(remote_filepath).await;
+	}
+
+	pub(crate) fn prepare_upload_options(&self, remote_filepath: &RemotePath, options: Option<&BunnyCDNDataOptions>) -> BunnyCDNDataOptions {
+		let upload_headers: HashMap<String, String> = options
+			.and_then(|provided_options| provided_options.headers.clone())
+			.unwrap_or_default();
+		let mut content_type: Option<String> = options.and_then(|provided_options| provided_options.content_type.clone());
+		if content_type.is_none() {
+			content_type = upload_headers.iter()
+				.find(|(header_name, _)| header_name.eq_ignore_ascii_case(CONTENT_TYPE_HEADER_NAME))
+				.map(|(_, header_value)| header_value.to_string());
+		}
+		let used_content_type = content_type.unwrap_or_else(|| self.guess_content_type(remote_filepath));
+		return BunnyCDNDataOptions{
+			headers: Some(upload_headers),
+			content_type: Some(used_content_type),
+		};
+	}
+
+	/// The content type used for uploading a file, when none is provided explicitly.
+	/// Overrides registered with set_content_type_override take precedence over the
+	/// guess based on the extension. Unknown extensions fall back to application/octet-stream
+	pub fn guess_content_type(&self, remote_filepath: &RemotePath) -> String {
+		if let Some(extension) = remote_filepath.extension() {
+			if let Some(content_type_override) = self.content_type_overrides.get(&extension.to_lowercase()) {
+				return content_type_override.to_string();
+			}
+		}
+		return mime_guess::from_path(remote_filepath.to_string())
+			.first_raw()
+			.unwrap_or(ContentType::ApplicationOctetStream.name())
+			.to_string();
 	}
 
 	/*
 		This is an abstraction for deleting files and directories
-		The reason for this is that directories require a trailing /, which is not
+		The reason for this is that directories require a trailing /, which is handled by RemotePath
 		Parameters:
 			entry_path: Either a directory or a filepath
 	 */
-	async fn handle_delete_entry(&self, entry_path: &str) -> Result<(), Error> {
+	async fn handle_delete_entry(&self, entry_path: &RemotePath) -> Result<(), Error> {
+		if !self.delete_entry_if_exists(entry_path).await? {
+			return Err(Error::new_from_message(&format!("Failed Deleting {} - Not Found", entry_path)));
+		}
+		return Ok(());
+	}
+
+	/*
+		Deletes a file or directory, returning false instead of an error if it does not exist.
+		Parameters:
+			entry_path: The file or directory relative to the root
+	*/
+	pub(crate) async fn delete_entry_if_exists(&self, entry_path: &RemotePath) -> Result<bool, Error> {
 		self.check_write_password_ok()?;
-		let delete_entry_path: String = self.validate_filepath(entry_path)?;
-		let delete_file_url: String = format!(
-			"{}/{}",
-			self.get_files_root_url(),
-			delete_entry_path
-		);
+		let delete_file_url: String = self.get_remote_url(entry_path);
 		let write_password = self.config.write_password.clone().unwrap();
-		let delete_file_result = self.delete(
+		let delete_file_result = self.delete_if_exists(
 			&delete_file_url,
 			&write_password
 		).await;
-		return delete_file_result;
+		self.invalidate_listing_cache(entry_path);
+		if !delete_file_result? {
+			return Ok(false);
+		}
+		self.purge_changed_path(entry_path).await?;
+		return Ok(true);
 	}
 
 	/*
@@ -252,53 +457,86 @@ impl BunnyCDNClient {
 			filepath: relative to the root
 	*/
 	pub async fn delete_file(&self, filepath: &str) -> Result<(), Error> {
-		if filepath.ends_with("/") {
+		if filepath.trim().ends_with("/") {
 			return Err(Error::new_from_message(&format!("Invalid Filepath - Provided: {}. Has a trailing /. Trying to delete a directory?", filepath)));
 		}
-		return self.handle_delete_entry(filepath).await;
+		let remote_filepath = RemotePath::file(filepath)?;
+		return self.handle_delete_entry(&remote_filepath).await;
 	}
 
 	/*
 		A directory path must be followed by a trailing /. Otherwise it will not be found even if it exists
 		For example if /my/test/directory exists on Bunnystorage and we want to delete it, then
 		we must supply the path /my/test/directory/
+		The root of the storage zone may not be deleted this way.
 	 */
 	pub async fn delete_directory(&self, directory_path: &str) -> Result<(), Error> {
-		if !directory_path.ends_with("/") {
+		if !directory_path.trim().ends_with("/") {
 			return Err(Error::new_from_message(&format!("Invalid Directory Path. Missing trailing / - Provided: {}", directory_path)));
 		}
-		return self.handle_delete_entry(directory_path).await;
+		let remote_directory = RemotePath::directory(directory_path)?;
+		if remote_directory.is_root() {
+			return Err(Error::new_from_message("Invalid Directory Path. Deleting the root of the storage zone is not allowed"));
+		}
+		return self.handle_delete_entry(&remote_directory).await;
 	}
 
-	fn derive_local_filepath(&self, remote_filepath: &str, local_filepath: &str) -> Result<String, Error> {
-		let derived_local_filepath = self.derive_filepath_common(remote_filepath, local_filepath)?;
-		return self.attempt_get_absolute_filepath(&derived_local_filepath);
+	/*
+		If the local filepath is an existing directory or has a trailing /, then the filename
+		of the remote file is appended to it. Otherwise it is the target file itself, which
+		allows for renaming the file, however the file extensions must match.
+	*/
+	async fn derive_local_filepath(&self, remote_filepath: &RemotePath, local_filepath: &str) -> Result<String, Error> {
+		let validated_local_filepath = self.validate_filepath(local_filepath)?;
+		let remote_filename = remote_filepath.file_name().unwrap_or("");
+		let local_is_directory = validated_local_filepath.ends_with("/") || fs::metadata(&validated_local_filepath)
+			.await
+			.is_ok_and(|local_metadata| local_metadata.is_dir());
+		if local_is_directory {
+			let derived_local_filepath = path::Path::new(&validated_local_filepath).join(remote_filename);
+			return self.attempt_get_absolute_filepath(&derived_local_filepath.to_string_lossy());
+		}
+		let local_file_extension: String = self.retrieve_filepath_extension(&validated_local_filepath);
+		let remote_file_extension: &str = remote_filepath.extension().unwrap_or("");
+		if !local_file_extension.is_empty() && !remote_file_extension.is_empty() && local_file_extension != remote_file_extension {
+			return Err(Error::new_from_message(&format!("Invalid Local File Extension - Expected {}, Received {}", remote_file_extension, local_file_extension)));
+		}
+		return self.attempt_get_absolute_filepath(&validated_local_filepath);
 	}
 
-	fn evaluate_local_target_filepath(&self, remote_filepath: &str, local_filepath: Option<&str>) -> Result<String, Error> {
+	async fn evaluate_local_target_filepath(&self, remote_filepath: &RemotePath, local_filepath: Option<&str>) -> Result<String, Error> {
 		match local_filepath {
-			None => self.attempt_get_absolute_filepath(remote_filepath),
+			None => self.attempt_get_absolute_filepath(&remote_filepath.to_string()),
 			Some(provided_local_filepath) => {
-				return self.derive_local_filepath(remote_filepath, provided_local_filepath);
+				return self.derive_local_filepath(remote_filepath, provided_local_filepath).await;
 			}
 		}
 	}
 
 	/*
-		This function handles streaming the contents of the file into either a vector or a file pointer.
-		In case of the latter is present, then the content will be consumed by the file and not be written
-		to the vector. If the file is not present the content will be added to a vector.
-		The vector is returned in both cases, but if a file is present, then it will be empty.
+		Sends the download request for a remote file and returns the response without
+		consuming the body, so that the caller may decide how to handle the byte stream
+		e.g. writing into a file or forwarding it to another upload.
 		Parameters:
 			remote_filepath: The filepath on bunnystorage relative to the root
-			file: file opened in another function, allows for streaming content into the file
 	*/
-	async fn handle_get_and_stream_file_contents(&self, remote_filepath: &str, mut file: Option<&mut fs::File>) -> Result<Vec<u8>, Error> {
-		let used_remote_filepath: String = self.prepare_remote_filepath(remote_filepath)?;
+	pub(crate) async fn open_remote_file_response(&self, remote_filepath: &RemotePath) -> Result<reqwest::Response, Error> {
+		let read_endpoint = self.read_endpoint().await;
+		return self.open_endpoint_file_response(&read_endpoint, remote_filepath).await;
+	}
+
+	/*
+		Sends the download request for a remote file to a specific endpoint, e.g. the configured
+		endpoint for reads that must see a preceding write.
+		Parameters:
+			endpoint: The storage endpoint to download from
+			remote_filepath: The filepath on bunnystorage relative to the root
+	*/
+	pub(crate) async fn open_endpoint_file_response(&self, endpoint: &StorageEndpoint, remote_filepath: &RemotePath) -> Result<reqwest::Response, Error> {
 		let download_file_url = format!(
 			"{}/{}",
-			self.get_files_root_url(),
-			used_remote_filepath,
+			self.get_endpoint_files_root_url(endpoint),
+			remote_filepath.to_url_path(),
 		);
 		let download_file_request = self.http_client.get(&download_file_url)
 			.header(ACCESS_KEY_HEADER_NAME, &self.config.read_password);
@@ -309,6 +547,20 @@ impl BunnyCDNClient {
 			.error_for_status()
 			.map_err(|http_get_file_error| Error::new_from_message(&http_get_file_error.to_string()))?;
 
+		return Ok(http_download_file_response);
+	}
+
+	/*
+		This function handles streaming the contents of the file into either a vector or a file pointer.
+		In case of the latter is present, then the content will be consumed by the file and not be written
+		to the vector. If the file is not present the content will be added to a vector.
+		The vector is returned in both cases, but if a file is present, then it will be empty.
+		Parameters:
+			remote_filepath: The filepath on bunnystorage relative to the root
+			file: file opened in another function, allows for streaming content into the file
+	*/
+	pub(crate) async fn handle_get_and_stream_file_contents(&self, remote_filepath: &RemotePath, mut file: Option<&mut BufWriter<fs::File>>) -> Result<Vec<u8>, Error> {
+		let http_download_file_response = self.open_remote_file_response(remote_filepath).await?;
 		// Setup 
 		let mut file_contents: Vec<u8> = Vec::new();
 		let mut file_stream = http_download_file_response.bytes_stream();
@@ -316,14 +568,14 @@ impl BunnyCDNClient {
 			if let Err(file_item_error) = file_item_result {
 				return Err(Error::new_from_message(&format!("Failed Stream Contents - Error: {}", file_item_error.to_string())));
 			}
-			let file_item_content = &file_item_result.unwrap().to_vec();
+			let file_item_content = file_item_result.unwrap();
 			if let Some(ref mut file_pointer) = file {
-				let write_file_result = file_pointer.write(file_item_content);
+				let write_file_result = file_pointer.write_all(&file_item_content).await;
 				if let Err(write_file_error) = write_file_result {
 					return Err(Error::new_from_message(&format!("Failed Write File Contents - Error: {}", write_file_error.to_string())));
 				}
 			} else {
-				file_contents.extend(file_item_content);
+				file_contents.extend_from_slice(&file_item_content);
 			}
 		}
 		return Ok(file_contents);
@@ -334,6 +586,7 @@ impl BunnyCDNClient {
 	/// will be thrown. 
 	/// 
 	/// Otherwise the contents will be streamed into the file to limit the memory footprint. 
+	/// The file is flushed and synced to disk before returning.
 	/// In case the streaming fails the file will be deleted.
 	///	
 	/// Parameters:
@@ -347,34 +600,269 @@ impl BunnyCDNClient {
 	/// ```
 	/// let downfile_file_result: Result<(), Error> = download_file(my_remote_filepath, my_local_filepath);
 	pub async fn download_file(&self, remote_filepath: &str, local_filepath: &str) -> Result<(), Error> {
-		let used_local_filepath: String = self.evaluate_local_target_filepath(remote_filepath, Some(local_filepath))?;
-		let mut local_file = fs::File::create(&used_local_filepath)
+		let used_remote_filepath = RemotePath::file(remote_filepath)?;
+		let used_local_filepath: String = self.evaluate_local_target_filepath(&used_remote_filepath, Some(local_filepath)).await?;
+		let local_file = fs::File::create(&used_local_filepath)
+			.await
 			.map_err(|open_file_error| Error::new_from_message(&open_file_error.to_string()))?;
 
-		let download_file_content_result = self.handle_get_and_stream_file_contents(remote_filepath, Some(&mut local_file)).await;
-		if let Err(download_file_content_error) = download_file_content_result {
-			_ = fs::remove_file(used_local_filepath);
-			return Err(download_file_content_error);
+		let mut local_file_writer = BufWriter::new(local_file);
+		let download_file_content_result = self.handle_get_file_contents(&used_remote_filepath, Some(&mut local_file_writer)).await;
+		let persist_file_result = match download_file_content_result {
+			Ok(_) => self.persist_local_file(&mut local_file_writer).await,
+			Err(download_file_content_error) => Err(download_file_content_error),
+		};
+		if let Err(persist_file_error) = persist_file_result {
+			drop(local_file_writer);
+			_ = fs::remove_file(used_local_filepath).await;
+			return Err(persist_file_error);
 		}
 		return Ok(());
 	}
+
+	/// Flushes the buffered contents and waits until the file has been written to disk
+	async fn persist_local_file(&self, local_file_writer: &mut BufWriter<fs::File>) -> Result<(), Error> {
+		local_file_writer.flush()
+			.await
+			.map_err(|flush_file_error| Error::new_from_message(&format!("Failed Flush File Contents - Error: {}", flush_file_error)))?;
+
+		local_file_writer.get_mut()
+			.sync_all()
+			.await
+			.map_err(|sync_file_error| Error::new_from_message(&format!("Failed Sync File Contents - Error: {}", sync_file_error)))?;
+
+		return Ok(());
+	}
 	
+	/// Retrieves part of a file from Bunnystorage. The range is exclusive, so 0..10 retrieves
+	/// the first 10 bytes. A range reaching past the end of the file is cut off at the end,
+	/// so a range starting past the end returns no bytes.
+	///
+	/// Parameters:
+	/// * remote_filepath: The filepath on bunnystorage
+	/// * range: The byte range to retrieve
+	pub async fn download_file_range(&self, remote_filepath: &str, range: Range<u64>) -> Result<Vec<u8>, Error> {
+		let used_remote_filepath = RemotePath::file(remote_filepath)?;
+		return self.get_remote_file_range(&used_remote_filepath, range).await;
+	}
+
+	pub(crate) async fn get_remote_file_range(&self, remote_filepath: &RemotePath, range: Range<u64>) -> Result<Vec<u8>, Error> {
+		return self.find_remote_file_range(remote_filepath, range).await?
+			.ok_or_else(|| Error::new_from_message(&format!("Failed Retrieving Range of {} - Not Found", remote_filepath)));
+	}
+
+	/// Retrieves part of a file like get_remote_file_range, but returns None if the file does not exist
+	pub(crate) async fn find_remote_file_range(&self, remote_filepath: &RemotePath, range: Range<u64>) -> Result<Option<Vec<u8>>, Error> {
+		if range.start >= range.end {
+			return Ok(Some(Vec::new()));
+		}
+		let download_range_url = self.get_read_url(remote_filepath).await;
+		let http_range_response = self.http_client.get(&download_range_url)
+			.header(ACCESS_KEY_HEADER_NAME, &self.config.read_password)
+			.header(RANGE, format!("bytes={}-{}", range.start, range.end - 1))
+			.send()
+			.await
+			.map_err(|http_range_error| Error::new_from_message(&http_range_error.to_string()))?;
+		// The range starts at or past the end of the file, so nothing is left after cutting it off
+		match http_range_response.status() {
+			StatusCode::NOT_FOUND => return Ok(None),
+			StatusCode::RANGE_NOT_SATISFIABLE => return Ok(Some(Vec::new())),
+			_ => {},
+		}
+		let http_range_response = http_range_response.error_for_status()
+			.map_err(|http_range_error| Error::new_from_message(&http_range_error.to_string()))?;
+
+		let http_range_status = http_range_response.status();
+		let range_content = http_range_response.bytes()
+			.await
+			.map_err(|range_content_error| Error::new_from_message(&format!("Failed Stream Contents - Error: {}", range_content_error)))?;
+
+		if http_range_status == StatusCode::PARTIAL_CONTENT {
+			return Ok(Some(range_content.to_vec()));
+		}
+		// The range was ignored and the entire file was returned
+		let content_length = range_content.len() as u64;
+		let range_start = range.start.min(content_length) as usize;
+		let range_end = range.end.min(content_length) as usize;
+		return Ok(Some(range_content[range_start..range_end].to_vec()));
+	}
+
 	/// This function retrieves the contents from Bunnystorage and add them to a vector
 	/// 
 	///	Parameters:
 	///	*	remote_filepath: The filepath on bunnystorage
 	/// Note: This is handled by the internal function 'handle_get_and_stream_file_contents'
 	pub async fn download_file_content(&self, remote_filepath: &str) -> Result<Vec<u8>, Error> {
-		return self.handle_get_and_stream_file_contents(
-			remote_filepath,
+		let used_remote_filepath = RemotePath::file(remote_filepath)?;
+		return self.handle_get_file_contents(
+			&used_remote_filepath,
 			None
 		).await;
 	}
 
+	fn parse_header_u64(&self, headers: &reqwest::header::HeaderMap, header_name: reqwest::header::HeaderName) -> Option<u64> {
+		return headers.get(header_name)
+			.and_then(|header_value| header_value.to_str().ok())
+			.and_then(|header_value| header_value.trim().parse::<u64>().ok());
+	}
+
+	/*
+		The total size of a ranged response is found after the / in the Content-Range header
+		e.g. "bytes 0-0/1234" for a satisfied range of the first byte
+	*/
+	fn parse_content_range_length(&self, headers: &reqwest::header::HeaderMap) -> Option<u64> {
+		return headers.get(CONTENT_RANGE)
+			.and_then(|header_value| header_value.to_str().ok())
+			.and_then(|header_value| header_value.rsplit_once('/'))
+			.and_then(|(_, total_length)| total_length.trim().parse::<u64>().ok());
+	}
+
+	fn parse_last_modified(&self, headers: &reqwest::header::HeaderMap) -> Option<DateTime<Utc>> {
+		return headers.get(LAST_MODIFIED)
+			.and_then(|header_value| header_value.to_str().ok())
+			.and_then(|header_value| DateTime::parse_from_rfc2822(header_value.trim()).ok())
+			.map(|last_modified| last_modified.with_timezone(&Utc));
+	}
+
+	/// Retrieves the size and content type of a remote file without listing its parent directory.
+	/// A HEAD request is attempted first. In case the endpoint does not support it, a ranged GET
+	/// for the first byte is used instead, so that the body is never transferred.
+	/// Returns None if the file does not exist.
+	///
+	/// Parameters:
+	/// * remote_filepath: The filepath on bunnystorage. Directories are not supported, use stat instead
+	pub async fn head_file(&self, remote_filepath: &str) -> Result<Option<RemoteFileHead>, Error> {
+		let used_remote_filepath = RemotePath::file(remote_filepath)?;
+		return self.head_remote_file(&used_remote_filepath).await;
+	}
+
+	pub(crate) async fn head_remote_file(&self, remote_filepath: &RemotePath) -> Result<Option<RemoteFileHead>, Error> {
+		let head_file_url = self.get_read_url(remote_filepath).await;
+		let http_head_response = self.http_client.head(&head_file_url)
+			.header(ACCESS_KEY_HEADER_NAME, &self.config.read_password)
+			.send()
+			.await
+			.map_err(|http_head_error| Error::new_from_message(&http_head_error.to_string()))?;
+
+		let http_head_status = http_head_response.status();
+		if http_head_status == StatusCode::NOT_FOUND {
+			return Ok(None);
+		}
+		if http_head_status.is_success() {
+			let file_head = RemoteFileHead{
+				length: self.parse_header_u64(http_head_response.headers(), CONTENT_LENGTH).unwrap_or(0),
+				content_type: http_head_response.headers()
+					.get(CONTENT_TYPE)
+					.and_then(|header_value| header_value.to_str().ok())
+					.map(|header_value| header_value.to_string()),
+				last_modified: self.parse_last_modified(http_head_response.headers()),
+			};
+			return Ok(Some(file_head));
+		}
+		if http_head_status != StatusCode::METHOD_NOT_ALLOWED && http_head_status != StatusCode::NOT_IMPLEMENTED {
+			return Err(Error::new_from_message(&format!("Failed Retrieving File Head - Status: {}", http_head_status)));
+		}
+		// Fall back to requesting only the first byte
+		let http_range_response = self.http_client.get(&head_file_url)
+			.header(ACCESS_KEY_HEADER_NAME, &self.config.read_password)
+			.header(RANGE, "bytes=0-0")
+			.send()
+			.await
+			.map_err(|http_range_error| Error::new_from_message(&http_range_error.to_string()))?;
+
+		let http_range_status = http_range_response.status();
+		let range_headers = http_range_response.headers();
+		let range_length = match http_range_status {
+			StatusCode::NOT_FOUND => return Ok(None),
+			// An empty file cannot satisfy any range, but it does exist
+			StatusCode::RANGE_NOT_SATISFIABLE => self.parse_content_range_length(range_headers).unwrap_or(0),
+			StatusCode::PARTIAL_CONTENT => self.parse_content_range_length(range_headers).unwrap_or(0),
+			StatusCode::OK => self.parse_header_u64(range_headers, CONTENT_LENGTH).unwrap_or(0),
+			_ => return Err(Error::new_from_message(&format!("Failed Retrieving File Head - Status: {}", http_range_status))),
+		};
+		let file_head = RemoteFileHead{
+			length: range_length,
+			content_type: range_headers
+				.get(CONTENT_TYPE)
+				.and_then(|header_value| header_value.to_str().ok())
+				.map(|header_value| header_value.to_string()),
+			last_modified: self.parse_last_modified(range_headers),
+		};
+		return Ok(Some(file_head));
+	}
+
+	/// Retrieves a single file or directory without listing the parent of files.
+	/// Files are looked up with head_file, so only the name, path, length, content type and
+	/// last change of the returned entry are known. Use stat_detailed for the remaining fields,
+	/// e.g. the checksum. Directories must have a trailing / and are always looked up in the
+	/// listing of their parent. The root has no entry.
+	///
+	/// Parameters:
+	/// * remote_path: The filepath or directory path on bunnystorage
+	pub async fn stat(&self, remote_path: &str) -> Result<Option<File>, Error> {
+		let used_remote_path = RemotePath::parse(remote_path)?;
+		return self.stat_remote_path(&used_remote_path, false).await;
+	}
+
+	/// Retrieves the full listing entry of a single file or directory, including fields such as
+	/// the checksum and the replicated zones, by listing the parent directory
+	///
+	/// Parameters:
+	/// * remote_path: The filepath or directory path on bunnystorage
+	pub async fn stat_detailed(&self, remote_path: &str) -> Result<Option<File>, Error> {
+		let used_remote_path = RemotePath::parse(remote_path)?;
+		return self.stat_remote_path(&used_remote_path, true).await;
+	}
+
+	/*
+		Looks up a single file or directory.
+		Parameters:
+			remote_path: The filepath or directory path on bunnystorage
+			include_listing: List the parent of files for the full entry instead of only using head_file
+	*/
+	pub(crate) async fn stat_remote_path(&self, remote_path: &RemotePath, include_listing: bool) -> Result<Option<File>, Error> {
+		if remote_path.is_file() && !include_listing {
+			let file_head_opt = self.head_remote_file(remote_path).await?;
+			return Ok(file_head_opt.map(|file_head| File::new_from_head(
+				&self.config.storage_zone_name,
+				remote_path,
+				file_head.length,
+				file_head.content_type.as_deref(),
+				file_head.last_modified,
+			)));
+		}
+		let remote_entry_opt = self.find_remote_entry(remote_path).await?;
+		if let Some(remote_entry) = &remote_entry_opt {
+			if remote_entry.is_directory() != remote_path.is_directory() {
+				return Ok(None);
+			}
+		}
+		return Ok(remote_entry_opt);
+	}
+
+	/// Checks if a file or directory exists. Files only require a single HEAD request,
+	/// directories must have a trailing / and are looked up in the listing of their parent.
+	///
+	/// Parameters:
+	/// * remote_path: The filepath or directory path on bunnystorage
+	pub async fn exists(&self, remote_path: &str) -> Result<bool, Error> {
+		let used_remote_path = RemotePath::parse(remote_path)?;
+		if used_remote_path.is_root() {
+			return Ok(true);
+		}
+		if used_remote_path.is_directory() {
+			let remote_directory = self.stat_remote_path(&used_remote_path, true).await?;
+			return Ok(remote_directory.is_some());
+		}
+		let file_head = self.head_remote_file(&used_remote_path).await?;
+		return Ok(file_head.is_some());
+	}
+
 }
 
 #[cfg(test)]
 mod files_tests {
+	use crate::client::BunnyCDNClientConfig;
 	use super::*;
 
 	#[tokio::test]
@@ -456,15 +944,29 @@ mod files_tests {
 			).await;
 		assert!(upload_image_root_target_result.is_ok());
 		// With Directory as Target
-		let test_directory_remote_filepath: &str = "/NewFolder";
-		let upload_image_root_target_result = client_result
+		let test_directory_remote_filepath: &str = "/NewFolder/";
+		let upload_image_directory_target_result = client_result
 			.as_ref()
 			.unwrap()
 			.upload_file(
 				test_image_file,
 				Some(test_directory_remote_filepath)
 			).await;
-		assert!(upload_image_root_target_result.is_ok());
+		assert!(upload_image_directory_target_result.is_ok());
+		let test_directory_target_exists_result = client_result.as_ref().unwrap().exists("/NewFolder/Test_Image.jpg").await;
+		assert!(test_directory_target_exists_result.is_ok_and(|file_exists| file_exists));
+		// Without Trailing / as Target, which is a file
+		let test_no_trailing_slash_remote_filepath: &str = "/NewFile";
+		let upload_image_no_trailing_slash_result = client_result
+			.as_ref()
+			.unwrap()
+			.upload_file(
+				test_image_file,
+				Some(test_no_trailing_slash_remote_filepath)
+			).await;
+		assert!(upload_image_no_trailing_slash_result.is_ok());
+		let test_no_trailing_slash_stat_result = client_result.as_ref().unwrap().stat(test_no_trailing_slash_remote_filepath).await;
+		assert!(test_no_trailing_slash_stat_result.is_ok_and(|remote_file| remote_file.is_some_and(|remote_file| remote_file.remote_path() == "NewFile")));
 		// With Valid Target and New Name to File
 		let test_valid_remote_filepath: &str = "/tests/files/source/new/New_Test_Image_Name.jpg";
 		let upload_image_valid_target_result = client_result
@@ -477,6 +979,62 @@ mod files_tests {
 		assert!(upload_image_valid_target_result.is_ok());
 	}
 
+	#[test]
+	fn test_evaluate_remote_target_filepath() {
+		let client = BunnyCDNClient::new(BunnyCDNClientConfig::new_offline()).unwrap();
+		let test_image_file: &str = "./tests/files/source/Test_Image.jpg";
+		let directory_target_result = client.evaluate_remote_target_filepath(test_image_file, Some("/NewFolder/"));
+		assert!(directory_target_result.is_ok_and(|remote_target| remote_target.to_string() == "NewFolder/Test_Image.jpg"));
+		let file_target_result = client.evaluate_remote_target_filepath(test_image_file, Some("/NewFolder"));
+		assert!(file_target_result.is_ok_and(|remote_target| remote_target.to_string() == "NewFolder"));
+		assert!(client.evaluate_remote_target_filepath(test_image_file, Some("/NewFolder/Test_Image.jpeg")).is_err());
+	}
+
+	#[test]
+	fn test_guess_content_type() {
+		let mut client = BunnyCDNClient::new(BunnyCDNClientConfig::new_offline()).unwrap();
+		let test_javascript_filepath = RemotePath::parse("/assets/app.JS").unwrap();
+		assert_eq!(client.guess_content_type(&test_javascript_filepath), "text/javascript");
+		let test_unknown_filepath = RemotePath::parse("/assets/archive.unknownextension").unwrap();
+		assert_eq!(client.guess_content_type(&test_unknown_filepath), ContentType::ApplicationOctetStream.name());
+		client.set_content_type_override(".unknownextension", "application/x-custom");
+		assert_eq!(client.guess_content_type(&test_unknown_filepath), "application/x-custom");
+		// An explicit content type wins over both the header and the guess
+		let mut test_headers = HashMap::<String, String>::new();
+		test_headers.insert("content-type".to_string(), "text/plain".to_string());
+		test_headers.insert("Cache-Control".to_string(), "max-age=60".to_string());
+		let test_options = BunnyCDNDataOptions{
+			headers: Some(test_headers),
+			content_type: None,
+		};
+		let prepared_options = client.prepare_upload_options(&test_javascript_filepath, Some(&test_options));
+		assert_eq!(prepared_options.content_type.as_deref(), Some("text/plain"));
+	}
+
+	#[tokio::test]
+	async fn test_stat_and_exists() {
+		let client_result: Result<BunnyCDNClient, Error> = BunnyCDNClient::new_from_env();
+		assert!(client_result.is_ok());
+		let client = client_result.unwrap();
+		let test_image_file: &str = "./tests/files/source/Test_Image.jpg";
+		let test_stat_remote_filepath: &str = "/tests/files/stat/Test_Image.jpg";
+		let test_upload_image_result = client.upload_file(test_image_file, Some(test_stat_remote_filepath)).await;
+		assert!(test_upload_image_result.is_ok());
+		// Existing File
+		let test_exists_result = client.exists(test_stat_remote_filepath).await;
+		assert!(test_exists_result.is_ok_and(|file_exists| file_exists));
+		let test_stat_result = client.stat(test_stat_remote_filepath).await;
+		assert!(test_stat_result.is_ok_and(|remote_file| remote_file.is_some_and(|remote_file| !remote_file.is_directory())));
+		let test_stat_detailed_result = client.stat_detailed(test_stat_remote_filepath).await;
+		assert!(test_stat_detailed_result.is_ok_and(|remote_file| remote_file.is_some_and(|remote_file| !remote_file.guid().is_empty())));
+		// Existing Directory
+		let test_stat_directory_result = client.stat("/tests/files/stat/").await;
+		assert!(test_stat_directory_result.is_ok_and(|remote_directory| remote_directory.is_some()));
+		// Non Existing File
+		let test_non_existant_result = client.exists("/tests/files/stat/No_Such_File.json").await;
+		assert!(test_non_existant_result.is_ok_and(|file_exists| !file_exists));
+	}
+
 	#[tokio::test]
 	async fn test_delete_file() {
 		let client_result: Result<BunnyCDNClient, Error> = BunnyCDNClient::new_from_env();
@@ -0,0 +1,366 @@
+use std::{io::SeekFrom, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+use tokio::{fs, io::{AsyncSeekExt, AsyncWriteExt}};
+
+use crate::{errors::Error, models::inventory::{InventoryRecord, INVENTORY_CSV_HEADER}, remotepath::RemotePath};
+
+use super::BunnyCDNClient;
+
+fn map_inventory_io_error(io_error: std::io::Error) -> Error {
+	return Error::new_from_message(&format!("Failed Writing Inventory - Error: {}", io_error));
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InventoryFormat {
+	#[default]
+	Csv,
+	// A JSON object per line
+	JsonLines,
+	// Requires the parquet feature. Parquet inventories cannot be resumed
+	#[cfg(feature = "parquet")]
+	Parquet,
+}
+
+#[derive(Default)]
+pub struct InventoryParameters {
+	pub format: InventoryFormat,
+	// Where the progress is stored after each directory. If the checkpoint exists when starting
+	// the export, then the export continues from the checkpoint instead of starting over.
+	// The checkpoint is removed once the export has completed
+	pub checkpoint_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InventorySummary {
+	pub records: u64,
+	pub total_bytes: u64,
+	pub directories: u64,
+	// Whether the export was continued from a checkpoint
+	pub resumed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InventoryCheckpoint {
+	prefix: String,
+	format: InventoryFormat,
+	// The length of the output once the records up to the checkpoint have been written.
+	// Anything written after the checkpoint is truncated when resuming
+	output_bytes: u64,
+	records: u64,
+	total_bytes: u64,
+	directories: u64,
+	// The directories which have not been listed yet
+	pending_directories: Vec<String>,
+}
+
+async fn load_inventory_checkpoint(checkpoint_path: &Path) -> Result<Option<InventoryCheckpoint>, Error> {
+	let checkpoint_content = match fs::read(checkpoint_path).await {
+		Ok(checkpoint_content) => checkpoint_content,
+		Err(read_error) if read_error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+		Err(read_error) => return Err(map_inventory_io_error(read_error)),
+	};
+	let checkpoint = serde_json::from_slice(&checkpoint_content)
+		.map_err(|parse_error| Error::new_from_message(&format!("Invalid Inventory Checkpoint {} - Error: {}", checkpoint_path.display(), parse_error)))?;
+	return Ok(Some(checkpoint));
+}
+
+async fn save_inventory_checkpoint(checkpoint_path: &Path, checkpoint: &InventoryCheckpoint) -> Result<(), Error> {
+	let checkpoint_content = serde_json::to_vec(checkpoint)
+		.map_err(|serialize_error| Error::new_from_message(&format!("Failed Serializing Inventory Checkpoint - Error: {}", serialize_error)))?;
+	// Replaced in a single step, so an interrupted write never corrupts the previous checkpoint
+	let mut temp_checkpoint_path = checkpoint_path.as_os_str().to_owned();
+	temp_checkpoint_path.push(".tmp");
+	fs::write(&temp_checkpoint_path, checkpoint_content).await.map_err(map_inventory_io_error)?;
+	fs::rename(&temp_checkpoint_path, checkpoint_path).await.map_err(map_inventory_io_error)?;
+	return Ok(());
+}
+
+/// Encodes the records as CSV rows without the header or as JSON lines
+pub(crate) fn encode_inventory_records(format: InventoryFormat, records: &[InventoryRecord]) -> Result<Vec<u8>, Error> {
+	let mut encoded_records: Vec<u8> = Vec::new();
+	match format {
+		InventoryFormat::Csv => {
+			let mut csv_writer = csv::WriterBuilder::new().has_headers(false).from_writer(&mut encoded_records);
+			for record in records.iter() {
+				csv_writer.write_record(record.csv_row())
+					.map_err(|csv_error| Error::new_from_message(&format!("Failed Writing Inventory - Error: {}", csv_error)))?;
+			}
+			csv_writer.flush().map_err(map_inventory_io_error)?;
+		},
+		InventoryFormat::JsonLines => {
+			for record in records.iter() {
+				serde_json::to_writer(&mut encoded_records, record)
+					.map_err(|serialize_error| Error::new_from_message(&format!("Failed Writing Inventory - Error: {}", serialize_error)))?;
+				encoded_records.push(b'\n');
+			}
+		},
+		#[cfg(feature = "parquet")]
+		InventoryFormat::Parquet => return Err(Error::new_from_message("Parquet Inventories Are Not Line Based")),
+	}
+	return Ok(encoded_records);
+}
+
+#[cfg(feature = "parquet")]
+fn build_inventory_record_batch(records: &[InventoryRecord]) -> Result<arrow_array::RecordBatch, Error> {
+	use std::sync::Arc;
+	use arrow_array::{ArrayRef, RecordBatch, StringArray, TimestampMillisecondArray, UInt64Array};
+
+	let record_batch = RecordBatch::try_from_iter([
+		("path", Arc::new(StringArray::from_iter_values(records.iter().map(|record| record.path.as_str()))) as ArrayRef),
+		("length", Arc::new(UInt64Array::from_iter_values(records.iter().map(|record| record.length))) as ArrayRef),
+		("checksum", Arc::new(StringArray::from_iter(records.iter().map(|record| record.checksum.as_deref()))) as ArrayRef),
+		("content_type", Arc::new(StringArray::from_iter_values(records.iter().map(|record| record.content_type.as_str()))) as ArrayRef),
+		("date_created", Arc::new(TimestampMillisecondArray::from_iter_values(records.iter().map(|record| record.date_created.timestamp_millis())).with_timezone("UTC")) as ArrayRef),
+		("last_changed", Arc::new(TimestampMillisecondArray::from_iter_values(records.iter().map(|record| record.last_changed.timestamp_millis())).with_timezone("UTC")) as ArrayRef),
+		("replicated_zones", Arc::new(StringArray::from_iter_values(records.iter().map(|record| record.replicated_zones.join(";")))) as ArrayRef),
+		("guid", Arc::new(StringArray::from_iter_values(records.iter().map(|record| record.guid.as_str()))) as ArrayRef),
+	]).map_err(|arrow_error| Error::new_from_message(&format!("Failed Writing Inventory - Error: {}", arrow_error)))?;
+	return Ok(record_batch);
+}
+
+enum InventorySink {
+	Text{
+		file: fs::File,
+		format: InventoryFormat,
+		output_bytes: u64,
+	},
+	#[cfg(feature = "parquet")]
+	Parquet(parquet::arrow::ArrowWriter<std::fs::File>),
+}
+
+impl InventorySink {
+
+	async fn create(output_path: &Path, format: InventoryFormat) -> Result<InventorySink, Error> {
+		#[cfg(feature = "parquet")]
+		if format == InventoryFormat::Parquet {
+			let output_file = std::fs::File::create(output_path).map_err(map_inventory_io_error)?;
+			let inventory_schema = build_inventory_record_batch(&[])?.schema();
+			let parquet_writer = parquet::arrow::ArrowWriter::try_new(output_file, inventory_schema, None)
+				.map_err(|parquet_error| Error::new_from_message(&format!("Failed Writing Inventory - Error: {}", parquet_error)))?;
+			return Ok(InventorySink::Parquet(parquet_writer));
+		}
+		let mut output_file = fs::File::create(output_path).await.map_err(map_inventory_io_error)?;
+		let mut output_bytes = 0;
+		if format == InventoryFormat::Csv {
+			let mut csv_header: Vec<u8> = Vec::new();
+			let mut csv_writer = csv::Writer::from_writer(&mut csv_header);
+			csv_writer.write_record(INVENTORY_CSV_HEADER)
+				.map_err(|csv_error| Error::new_from_message(&format!("Failed Writing Inventory - Error: {}", csv_error)))?;
+			csv_writer.flush().map_err(map_inventory_io_error)?;
+			drop(csv_writer);
+			output_file.write_all(&csv_header).await.map_err(map_inventory_io_error)?;
+			output_bytes = csv_header.len() as u64;
+		}
+		return Ok(InventorySink::Text{
+			file: output_file,
+			format,
+			output_bytes,
+		});
+	}
+
+	async fn resume(output_path: &Path, checkpoint: &InventoryCheckpoint) -> Result<InventorySink, Error> {
+		let mut output_file = fs::OpenOptions::new().write(true).open(output_path).await.map_err(map_inventory_io_error)?;
+		output_file.set_len(checkpoint.output_bytes).await.map_err(map_inventory_io_error)?;
+		output_file.seek(SeekFrom::End(0)).await.map_err(map_inventory_io_error)?;
+		return Ok(InventorySink::Text{
+			file: output_file,
+			format: checkpoint.format,
+			output_bytes: checkpoint.output_bytes,
+		});
+	}
+
+	// Returns the length of the output after writing the records
+	async fn write_records(&mut self, records: &[InventoryRecord]) -> Result<u64, Error> {
+		match self {
+			InventorySink::Text{ file, format, output_bytes } => {
+				let encoded_records = encode_inventory_records(*format, records)?;
+				file.write_all(&encoded_records).await.map_err(map_inventory_io_error)?;
+				file.flush().await.map_err(map_inventory_io_error)?;
+				*output_bytes += encoded_records.len() as u64;
+				return Ok(*output_bytes);
+			},
+			#[cfg(feature = "parquet")]
+			InventorySink::Parquet(parquet_writer) => {
+				if !records.is_empty() {
+					parquet_writer.write(&build_inventory_record_batch(records)?)
+						.map_err(|parquet_error| Error::new_from_message(&format!("Failed Writing Inventory - Error: {}", parquet_error)))?;
+				}
+				return Ok(parquet_writer.bytes_written() as u64);
+			},
+		}
+	}
+
+	async fn finish(self) -> Result<(), Error> {
+		match self {
+			InventorySink::Text{ mut file, .. } => {
+				file.flush().await.map_err(map_inventory_io_error)?;
+				file.sync_all().await.map_err(map_inventory_io_error)?;
+			},
+			#[cfg(feature = "parquet")]
+			InventorySink::Parquet(parquet_writer) => {
+				parquet_writer.close()
+					.map_err(|parquet_error| Error::new_from_message(&format!("Failed Writing Inventory - Error: {}", parquet_error)))?;
+			},
+		}
+		return Ok(());
+	}
+}
+
+impl BunnyCDNClient {
+
+	/// Walks every directory below the prefix and writes a record per file to the output.
+	/// Directories are listed one at a time, so the inventory is never held in memory as a whole.
+	///
+	/// With a checkpoint path the progress is stored after each directory, so an interrupted
+	/// export of a large zone continues where it stopped when called again with the same arguments
+	///
+	/// Parameters:
+	/// * prefix: The directory to export, including its subdirectories. Use / for the whole zone
+	/// * output_path: The local file the inventory is written to. Overwritten unless resuming
+	/// * params: The format and the checkpoint
+	pub async fn export_inventory(&self, prefix: &str, output_path: &str, params: &InventoryParameters) -> Result<InventorySummary, Error> {
+		let remote_directory = RemotePath::directory(prefix)?;
+		let output_path = Path::new(output_path);
+		let existing_checkpoint = match &params.checkpoint_path {
+			Some(checkpoint_path) => load_inventory_checkpoint(checkpoint_path).await?,
+			None => None,
+		};
+		#[cfg(feature = "parquet")]
+		if params.format == InventoryFormat::Parquet && params.checkpoint_path.is_some() {
+			return Err(Error::new_from_message("Parquet Inventories Cannot Be Resumed - Remove the checkpoint path"));
+		}
+		let resumed = existing_checkpoint.is_some();
+		let (mut checkpoint, mut inventory_sink) = match existing_checkpoint {
+			Some(checkpoint) => {
+				if checkpoint.prefix != remote_directory.to_string() || checkpoint.format != params.format {
+					return Err(Error::new_from_message(&format!(
+						"Inventory Checkpoint Mismatch - The checkpoint is for {} as {:?}",
+						checkpoint.prefix,
+						checkpoint.format,
+					)));
+				}
+				let inventory_sink = InventorySink::resume(output_path, &checkpoint).await?;
+				(checkpoint, inventory_sink)
+			},
+			None => {
+				let inventory_sink = InventorySink::create(output_path, params.format).await?;
+				let checkpoint = InventoryCheckpoint{
+					prefix: remote_directory.to_string(),
+					format: params.format,
+					output_bytes: 0,
+					records: 0,
+					total_bytes: 0,
+					directories: 0,
+					pending_directories: vec![remote_directory.to_string()],
+				};
+				(checkpoint, inventory_sink)
+			},
+		};
+		while let Some(pending_directory) = checkpoint.pending_directories.last() {
+			let directory_entries = self.get_remote_directory_entries(&RemotePath::directory(pending_directory)?).await?;
+			let mut records: Vec<InventoryRecord> = Vec::new();
+			let mut subdirectories: Vec<String> = Vec::new();
+			for directory_entry in directory_entries.iter() {
+				if directory_entry.is_directory() {
+					subdirectories.push(directory_entry.remote_path());
+				} else {
+					records.push(InventoryRecord::from(directory_entry));
+				}
+			}
+			records.sort_by(|record, other_record| record.path.cmp(&other_record.path));
+			checkpoint.output_bytes = inventory_sink.write_records(&records).await?;
+			checkpoint.records += records.len() as u64;
+			checkpoint.total_bytes += records.iter().map(|record| record.length).sum::<u64>();
+			checkpoint.directories += 1;
+			// Reversed, so the directories are visited in alphabetical order
+			subdirectories.sort_by(|subdirectory, other_subdirectory| other_subdirectory.cmp(subdirectory));
+			checkpoint.pending_directories.pop();
+			checkpoint.pending_directories.extend(subdirectories);
+			if let Some(checkpoint_path) = &params.checkpoint_path {
+				save_inventory_checkpoint(checkpoint_path, &checkpoint).await?;
+			}
+		}
+		inventory_sink.finish().await?;
+		if let Some(checkpoint_path) = &params.checkpoint_path {
+			fs::remove_file(checkpoint_path).await.map_err(map_inventory_io_error)?;
+		}
+		return Ok(InventorySummary{
+			records: checkpoint.records,
+			total_bytes: checkpoint.total_bytes,
+			directories: checkpoint.directories,
+			resumed,
+		});
+	}
+
+}
+
+#[cfg(test)]
+mod inventory_tests {
+	use crate::models::file::File;
+	use super::*;
+
+	const TEST_FILE_JSON: &str = r#"{
+		"Guid": "6b0b7c2a-5d1f-4a43-9f0e-4d7d0f3f1a11",
+		"StorageZoneName": "myzone",
+		"Path": "/myzone/images/",
+		"ObjectName": "logo, large.png",
+		"Length": 2048,
+		"LastChanged": "2024-03-01T10:00:00",
+		"ServerId": 12,
+		"ArrayNumber": 0,
+		"IsDirectory": false,
+		"UserId": "user",
+		"ContentType": "image/png",
+		"DateCreated": "2024-01-01T00:00:00",
+		"StorageZoneId": 1234,
+		"Checksum": "a665a45920422f9d417e4867efdc4fb8a04a1f3fff1fa07e998e86f7f7a27ae3",
+		"ReplicatedZones": "de,NY"
+	}"#;
+
+	#[test]
+	fn test_encode_inventory_records() {
+		let test_file: File = serde_json::from_str(TEST_FILE_JSON).unwrap();
+		let test_record = InventoryRecord::from(&test_file);
+		assert_eq!(test_record.path, "images/logo, large.png");
+		assert_eq!(test_record.checksum.as_deref(), Some("A665A45920422F9D417E4867EFDC4FB8A04A1F3FFF1FA07E998E86F7F7A27AE3"));
+		assert_eq!(test_record.replicated_zones, vec!["DE", "NY"]);
+		let encoded_csv = String::from_utf8(encode_inventory_records(InventoryFormat::Csv, std::slice::from_ref(&test_record)).unwrap()).unwrap();
+		assert!(encoded_csv.starts_with("\"images/logo, large.png\",2048,A665"));
+		assert!(encoded_csv.ends_with(",DE;NY,6b0b7c2a-5d1f-4a43-9f0e-4d7d0f3f1a11\n"));
+		let encoded_json_lines = encode_inventory_records(InventoryFormat::JsonLines, &[test_record.clone(), test_record.clone()]).unwrap();
+		let decoded_records: Vec<InventoryRecord> = encoded_json_lines.split(|byte| *byte == b'\n')
+			.filter(|line| !line.is_empty())
+			.map(|line| serde_json::from_slice(line).unwrap())
+			.collect();
+		assert_eq!(decoded_records, vec![test_record.clone(), test_record]);
+	}
+
+	#[cfg(feature = "parquet")]
+	#[test]
+	fn test_build_inventory_record_batch() {
+		let test_file: File = serde_json::from_str(TEST_FILE_JSON).unwrap();
+		let test_records = vec![InventoryRecord::from(&test_file); 3];
+		let record_batch = build_inventory_record_batch(&test_records).unwrap();
+		assert_eq!(record_batch.num_rows(), 3);
+		assert_eq!(record_batch.num_columns(), INVENTORY_CSV_HEADER.len());
+	}
+
+	#[tokio::test]
+	async fn test_export_inventory() {
+		let client_result: Result<BunnyCDNClient, Error> = BunnyCDNClient::new_from_env();
+		assert!(client_result.is_ok());
+		let client = client_result.unwrap();
+		let inventory_path = std::env::temp_dir().join("bunnystorage-inventory-test.csv");
+		let checkpoint_path = std::env::temp_dir().join("bunnystorage-inventory-test.checkpoint");
+		let inventory_params = InventoryParameters{
+			format: InventoryFormat::Csv,
+			checkpoint_path: Some(checkpoint_path.clone()),
+		};
+		let inventory_summary_result = client.export_inventory("tests/", inventory_path.to_str().unwrap(), &inventory_params).await;
+		assert!(inventory_summary_result.is_ok());
+		assert!(!checkpoint_path.exists());
+		_ = std::fs::remove_file(inventory_path);
+	}
+}
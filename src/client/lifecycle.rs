@@ -0,0 +1,240 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Duration, Utc};
+use glob::{MatchOptions, Pattern};
+
+use crate::{errors::Error, models::file::File, remotepath::RemotePath};
+
+use super::{safedelete::DeleteFailure, BunnyCDNClient};
+
+const LIFECYCLE_PATTERN_MATCH_OPTIONS: MatchOptions = MatchOptions{
+	case_sensitive: true,
+	require_literal_separator: true,
+	require_literal_leading_dot: false,
+};
+
+/// The timestamp of a file which lifecycle rules are evaluated against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LifecycleTimestamp {
+	#[default]
+	LastChanged,
+	DateCreated,
+}
+
+impl LifecycleTimestamp {
+
+	fn of(&self, file: &File) -> DateTime<Utc> {
+		return match self {
+			LifecycleTimestamp::LastChanged => file.last_changed(),
+			LifecycleTimestamp::DateCreated => file.date_created(),
+		};
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LifecycleAction {
+	// Expires every matching file older than the duration
+	ExpireOlderThan(Duration),
+	// Keeps the provided number of newest matching files and expires the rest
+	KeepNewest(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct LifecycleRule {
+	// Identifies the rule in the report
+	pub name: String,
+	// The directory the rule applies to, including its subdirectories
+	pub prefix: String,
+	// A glob pattern relative to the root of the storage zone e.g. backups/*.tar.zst
+	// If None, then every file below the prefix matches
+	pub pattern: Option<String>,
+	pub action: LifecycleAction,
+	pub timestamp: LifecycleTimestamp,
+}
+
+#[derive(Debug, Clone)]
+pub struct LifecycleExpiration {
+	pub rule: String,
+	pub path: String,
+	pub length: u64,
+	// The timestamp the rule was evaluated against
+	pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Default)]
+pub struct LifecycleReport {
+	pub dry_run: bool,
+	// Every file expired by a rule. A file expired by multiple rules is only listed for the first
+	pub expired: Vec<LifecycleExpiration>,
+	// The paths of the files which were deleted. Always empty for a dry run
+	pub deleted: Vec<String>,
+	pub failed: Vec<DeleteFailure>,
+}
+
+impl LifecycleReport {
+
+	pub fn expired_bytes(&self) -> u64 {
+		return self.expired.iter().map(|expiration| expiration.length).sum();
+	}
+}
+
+/// Selects the files expired by the rule out of the files below its prefix
+pub(crate) fn evaluate_lifecycle_rule(rule: &LifecycleRule, files: &[File], now: DateTime<Utc>) -> Result<Vec<LifecycleExpiration>, Error> {
+	let rule_pattern = match &rule.pattern {
+		Some(pattern) => {
+			let used_pattern = pattern.trim().trim_start_matches("./").trim_start_matches('/');
+			let compiled_pattern = Pattern::new(used_pattern)
+				.map_err(|pattern_error| Error::new_from_message(&format!("Invalid Lifecycle Pattern {} - Error: {}", pattern, pattern_error)))?;
+			Some(compiled_pattern)
+		},
+		None => None,
+	};
+	let mut matched_files: Vec<(&File, String)> = Vec::new();
+	for file in files.iter().filter(|file| !file.is_directory()) {
+		let remote_filepath = file.remote_path();
+		let is_match = rule_pattern.as_ref()
+			.is_none_or(|rule_pattern| rule_pattern.matches_with(&remote_filepath, LIFECYCLE_PATTERN_MATCH_OPTIONS));
+		if is_match {
+			matched_files.push((file, remote_filepath));
+		}
+	}
+	let expired_files: Vec<(&File, String)> = match &rule.action {
+		LifecycleAction::ExpireOlderThan(max_age) => matched_files.into_iter()
+			.filter(|(file, _)| now - rule.timestamp.of(file) > *max_age)
+			.collect(),
+		LifecycleAction::KeepNewest(keep_count) => {
+			matched_files.sort_by(|(file, remote_filepath), (other_file, other_remote_filepath)| rule.timestamp.of(other_file).cmp(&rule.timestamp.of(file))
+				.then_with(|| remote_filepath.cmp(other_remote_filepath)));
+			matched_files.into_iter().skip(*keep_count).collect()
+		},
+	};
+	return Ok(expired_files.into_iter()
+		.map(|(file, remote_filepath)| LifecycleExpiration{
+			rule: rule.name.clone(),
+			path: remote_filepath,
+			length: file.length(),
+			timestamp: rule.timestamp.of(file),
+		})
+		.collect());
+}
+
+impl BunnyCDNClient {
+
+	/// Evaluates the lifecycle rules over the listing of the configured endpoint and deletes the expired files.
+	/// Bunnystorage has no lifecycle rules of its own, so this is meant to be run periodically.
+	///
+	/// # Examples
+	/// ```no_run
+	/// use bunnystorage_rs::client::{BunnyCDNClient, lifecycle::{LifecycleAction, LifecycleRule, LifecycleTimestamp}};
+	/// # async fn example() -> Result<(), bunnystorage_rs::errors::Error> {
+	/// let client = BunnyCDNClient::new_from_env()?;
+	/// let rules = vec![
+	///     LifecycleRule{
+	///         name: "temporary exports".to_string(),
+	///         prefix: "tmp-exports/".to_string(),
+	///         pattern: None,
+	///         action: LifecycleAction::ExpireOlderThan(chrono::Duration::days(7)),
+	///         timestamp: LifecycleTimestamp::LastChanged,
+	///     },
+	///     LifecycleRule{
+	///         name: "backups".to_string(),
+	///         prefix: "backups/".to_string(),
+	///         pattern: Some("backups/*.tar.zst".to_string()),
+	///         action: LifecycleAction::KeepNewest(30),
+	///         timestamp: LifecycleTimestamp::DateCreated,
+	///     },
+	/// ];
+	/// let lifecycle_report = client.apply_lifecycle_rules(&rules, true).await?;
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Parameters:
+	/// * rules: The rules to evaluate. A file expired by multiple rules is deleted once
+	/// * dry_run: Only report the expired files without deleting anything
+	pub async fn apply_lifecycle_rules(&self, rules: &[LifecycleRule], dry_run: bool) -> Result<LifecycleReport, Error> {
+		if !dry_run {
+			self.check_write_password_ok()?;
+		}
+		let now = Utc::now();
+		let mut lifecycle_report = LifecycleReport{
+			dry_run,
+			..Default::default()
+		};
+		let mut expired_paths: HashSet<String> = HashSet::new();
+		for rule in rules.iter() {
+			let rule_directory = RemotePath::directory(&rule.prefix)?;
+			let rule_files = self.get_primary_files_recursive(&rule_directory).await?;
+			for expiration in evaluate_lifecycle_rule(rule, &rule_files, now)?.into_iter() {
+				if expired_paths.insert(expiration.path.clone()) {
+					lifecycle_report.expired.push(expiration);
+				}
+			}
+		}
+		if dry_run {
+			return Ok(lifecycle_report);
+		}
+		let purge_batch = self.begin_purge_batch();
+		for expiration in lifecycle_report.expired.iter() {
+			let delete_file_result = self.delete_file(&expiration.path).await;
+			match delete_file_result {
+				Ok(_) => lifecycle_report.deleted.push(expiration.path.clone()),
+				Err(delete_file_error) => lifecycle_report.failed.push(DeleteFailure{
+					path: expiration.path.clone(),
+					error: delete_file_error,
+				}),
+			}
+		}
+		purge_batch.finish_checked().await?;
+		return Ok(lifecycle_report);
+	}
+
+}
+
+#[cfg(test)]
+mod lifecycle_tests {
+	use crate::models::file::TestFileBuilder;
+	use super::*;
+
+	#[test]
+	fn test_evaluate_lifecycle_rule() {
+		let now = DateTime::parse_from_rfc3339("2024-03-10T00:00:00Z").unwrap().to_utc();
+		let test_files = vec![
+			TestFileBuilder::new("backups/2024-03-01.tar.zst").length(100).last_changed("2024-03-01T00:00:00").build(),
+			TestFileBuilder::new("backups/2024-03-05.tar.zst").length(100).last_changed("2024-03-05T00:00:00").build(),
+			TestFileBuilder::new("backups/2024-03-09.tar.zst").length(100).last_changed("2024-03-09T00:00:00").build(),
+			TestFileBuilder::new("backups/README.md").length(100).last_changed("2020-01-01T00:00:00").build(),
+			TestFileBuilder::new("backups/nested/2019-01-01.tar.zst").length(100).last_changed("2019-01-01T00:00:00").build(),
+		];
+		let keep_newest_rule = LifecycleRule{
+			name: "backups".to_string(),
+			prefix: "backups/".to_string(),
+			pattern: Some("backups/*.tar.zst".to_string()),
+			action: LifecycleAction::KeepNewest(2),
+			timestamp: LifecycleTimestamp::LastChanged,
+		};
+		let keep_newest_expirations = evaluate_lifecycle_rule(&keep_newest_rule, &test_files, now).unwrap();
+		let keep_newest_paths: Vec<&str> = keep_newest_expirations.iter().map(|expiration| expiration.path.as_str()).collect();
+		assert_eq!(keep_newest_paths, vec!["backups/2024-03-01.tar.zst"]);
+		let expire_rule = LifecycleRule{
+			name: "old".to_string(),
+			prefix: "backups/".to_string(),
+			pattern: None,
+			action: LifecycleAction::ExpireOlderThan(Duration::days(7)),
+			timestamp: LifecycleTimestamp::LastChanged,
+		};
+		let expire_expirations = evaluate_lifecycle_rule(&expire_rule, &test_files, now).unwrap();
+		assert_eq!(expire_expirations.len(), 3);
+		// Every file was created more than 7 days ago
+		let created_rule = LifecycleRule{
+			timestamp: LifecycleTimestamp::DateCreated,
+			..expire_rule.clone()
+		};
+		assert_eq!(evaluate_lifecycle_rule(&created_rule, &test_files, now).unwrap().len(), 5);
+		let invalid_rule = LifecycleRule{
+			pattern: Some("[".to_string()),
+			..expire_rule
+		};
+		assert!(evaluate_lifecycle_rule(&invalid_rule, &test_files, now).is_err());
+	}
+}
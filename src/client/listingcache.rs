@@ -0,0 +1,185 @@
+use std::{collections::HashMap, sync::{atomic::{AtomicU64, Ordering}, Mutex}, time::{Duration, Instant}};
+
+use crate::{models::file::File, remotepath::RemotePath};
+
+use super::BunnyCDNClient;
+
+struct ListingCacheEntry {
+	cached_at: Instant,
+	files: Vec<File>,
+}
+
+/// An in-memory cache of directory listings used by get_files and everything built on top of it.
+///
+/// Uploads and deletes made through the same client invalidate the listings of every affected
+/// directory. Changes made by other clients are only seen once the cached listing has expired.
+pub struct ListingCache {
+	ttl: Duration,
+	max_entries: usize,
+	entries: Mutex<HashMap<RemotePath, ListingCacheEntry>>,
+	// Increased by every invalidation, so listings fetched before a write are not cached after it
+	generation: AtomicU64,
+}
+
+impl ListingCache {
+
+	/// Parameters:
+	/// * ttl: How long a listing is used before it is retrieved again
+	/// * max_entries: The maximum number of cached directories. The oldest listings are removed first
+	pub fn new(ttl: Duration, max_entries: usize) -> ListingCache {
+		return ListingCache{
+			ttl,
+			max_entries,
+			entries: Mutex::new(HashMap::new()),
+			generation: AtomicU64::new(0),
+		};
+	}
+
+	pub fn ttl(&self) -> Duration {
+		return self.ttl;
+	}
+
+	pub fn max_entries(&self) -> usize {
+		return self.max_entries;
+	}
+
+	/// The number of cached directories, including expired listings which have not been removed yet
+	pub fn len(&self) -> usize {
+		return self.entries.lock().map_or(0, |entries| entries.len());
+	}
+
+	pub fn is_empty(&self) -> bool {
+		return self.len() == 0;
+	}
+
+	pub fn clear(&self) {
+		if let Ok(mut entries) = self.entries.lock() {
+			entries.clear();
+		}
+	}
+
+	pub(crate) fn get(&self, remote_directory: &RemotePath) -> Option<Vec<File>> {
+		let mut entries = self.entries.lock().ok()?;
+		let cache_entry = entries.get(remote_directory)?;
+		if cache_entry.cached_at.elapsed() >= self.ttl {
+			entries.remove(remote_directory);
+			return None;
+		}
+		return Some(cache_entry.files.clone());
+	}
+
+	/// The current invalidation generation. Read before fetching a listing and passed to insert
+	pub(crate) fn generation(&self) -> u64 {
+		return self.generation.load(Ordering::SeqCst);
+	}
+
+	/// Caches the listing of a directory, unless an invalidation happened since fetched_generation was read
+	pub(crate) fn insert(&self, remote_directory: &RemotePath, files: &[File], fetched_generation: u64) {
+		if self.max_entries == 0 {
+			return;
+		}
+		let Ok(mut entries) = self.entries.lock() else {
+			return;
+		};
+		// The listing may predate a write, so caching it would hide the write until the ttl expires
+		if self.generation.load(Ordering::SeqCst) != fetched_generation {
+			return;
+		}
+		if entries.len() >= self.max_entries && !entries.contains_key(remote_directory) {
+			entries.retain(|_, cache_entry| cache_entry.cached_at.elapsed() < self.ttl);
+		}
+		while entries.len() >= self.max_entries && !entries.contains_key(remote_directory) {
+			let oldest_directory = entries.iter()
+				.min_by_key(|(_, cache_entry)| cache_entry.cached_at)
+				.map(|(cached_directory, _)| cached_directory.clone());
+			match oldest_directory {
+				Some(oldest_directory) => entries.remove(&oldest_directory),
+				None => break,
+			};
+		}
+		entries.insert(remote_directory.as_directory(), ListingCacheEntry{
+			cached_at: Instant::now(),
+			files: files.to_vec(),
+		});
+	}
+
+	/// Removes the listings which may have changed by writing or deleting the path.
+	/// Every ancestor is affected, since writing a file may create its directories. When deleting
+	/// a directory, the listings of the directory and all of its subdirectories are removed as well
+	pub(crate) fn invalidate(&self, remote_path: &RemotePath) {
+		let Ok(mut entries) = self.entries.lock() else {
+			return;
+		};
+		self.generation.fetch_add(1, Ordering::SeqCst);
+		if remote_path.is_directory() {
+			entries.retain(|cached_directory, _| !cached_directory.starts_with(remote_path));
+		}
+		let mut parent_directory = remote_path.parent();
+		while let Some(remote_directory) = parent_directory {
+			entries.remove(&remote_directory);
+			parent_directory = remote_directory.parent();
+		}
+	}
+}
+
+impl BunnyCDNClient {
+
+	/// Places an in-memory cache in front of directory listings. Passing None disables the cache
+	pub fn set_listing_cache(&mut self, listing_cache: Option<ListingCache>) {
+		self.listing_cache = listing_cache;
+	}
+
+	pub fn listing_cache(&self) -> Option<&ListingCache> {
+		return self.listing_cache.as_ref();
+	}
+
+	pub(crate) fn invalidate_listing_cache(&self, remote_path: &RemotePath) {
+		if let Some(listing_cache) = &self.listing_cache {
+			listing_cache.invalidate(remote_path);
+		}
+	}
+
+}
+
+#[cfg(test)]
+mod listing_cache_tests {
+	use super::*;
+
+	#[test]
+	fn test_listing_cache() {
+		let listing_cache = ListingCache::new(Duration::from_secs(60), 2);
+		let images_directory = RemotePath::directory("images").unwrap();
+		let icons_directory = RemotePath::directory("images/icons").unwrap();
+		listing_cache.insert(&RemotePath::root(), &[], listing_cache.generation());
+		listing_cache.insert(&images_directory, &[], listing_cache.generation());
+		assert!(listing_cache.get(&images_directory).is_some());
+		// The oldest listing is removed when exceeding max_entries
+		listing_cache.insert(&icons_directory, &[], listing_cache.generation());
+		assert_eq!(listing_cache.len(), 2);
+		assert!(listing_cache.get(&RemotePath::root()).is_none());
+		// Uploading a file invalidates every ancestor
+		listing_cache.invalidate(&RemotePath::file("images/logo.png").unwrap());
+		assert!(listing_cache.get(&images_directory).is_none());
+		assert!(listing_cache.get(&icons_directory).is_some());
+		// Deleting a directory also invalidates its subdirectories
+		listing_cache.invalidate(&images_directory);
+		assert!(listing_cache.is_empty());
+		let expired_listing_cache = ListingCache::new(Duration::ZERO, 2);
+		expired_listing_cache.insert(&images_directory, &[], listing_cache.generation());
+		assert!(expired_listing_cache.get(&images_directory).is_none());
+	}
+
+	#[test]
+	fn test_skip_listing_fetched_before_invalidation() {
+		let listing_cache = ListingCache::new(Duration::from_secs(60), 4);
+		let images_directory = RemotePath::directory("images").unwrap();
+		// A listing is fetched, then a file is uploaded before the listing is inserted
+		let fetched_generation = listing_cache.generation();
+		listing_cache.invalidate(&RemotePath::file("images/logo.png").unwrap());
+		listing_cache.insert(&images_directory, &[], fetched_generation);
+		assert!(listing_cache.get(&images_directory).is_none());
+		// A listing fetched after the upload is cached
+		listing_cache.insert(&images_directory, &[], listing_cache.generation());
+		assert!(listing_cache.get(&images_directory).is_some());
+	}
+}
@@ -0,0 +1,122 @@
+use crate::{errors::Error, models::{hostname::Hostname, pullzone::PullZone}, remotepath::RemotePath};
+
+use super::{storagezones::GetStorageZoneParameters, BunnyCDNClient};
+
+/// The base URL of a pull zone, e.g. https://cdn.example.com. Custom hostnames are preferred over
+/// the system hostname, with custom hostnames with a certificate first. Hostnames with a certificate
+/// use https, since the system hostnames always have one
+pub(crate) fn select_public_url_base(hostnames: &[Hostname]) -> Option<String> {
+	let preferred_hostname = hostnames.iter()
+		.filter(|hostname| !hostname.value.trim().is_empty())
+		.min_by_key(|hostname| (hostname.is_system_hostname, !hostname.has_certificate))?;
+	let url_scheme = match preferred_hostname.has_certificate || preferred_hostname.is_system_hostname || preferred_hostname.force_ssl {
+		true => "https",
+		false => "http",
+	};
+	return Some(format!("{}://{}", url_scheme, preferred_hostname.value.trim().trim_end_matches('/')));
+}
+
+impl BunnyCDNClient {
+
+	/// Uses the provided base URL, e.g. https://cdn.example.com, for public URLs instead of looking up
+	/// the pull zone linked to the storage zone. Passing None looks up the pull zone again on the next call
+	pub fn set_public_url_base(&self, public_url_base: Option<&str>) {
+		if let Ok(mut cached_public_url_base) = self.public_url_base.lock() {
+			*cached_public_url_base = public_url_base.map(|public_url_base| public_url_base.trim_end_matches('/').to_string());
+		}
+	}
+
+	/// Every pull zone using the storage zone as its origin
+	pub(crate) async fn find_linked_pull_zones(&self) -> Result<Vec<PullZone>, Error> {
+		let storage_zone_params = GetStorageZoneParameters{
+			include_deleted: Some(false),
+			search: Some(self.config.storage_zone_name.clone()),
+		};
+		let storage_zones = self.get_storage_zones(Some(&storage_zone_params), None).await?;
+		let storage_zone = storage_zones.into_iter()
+			.find(|storage_zone| storage_zone.name == self.config.storage_zone_name && !storage_zone.deleted)
+			.ok_or_else(|| Error::new_from_message(&format!("Storage Zone Not Found - {}", self.config.storage_zone_name)))?;
+		let mut linked_pull_zones: Vec<PullZone> = Vec::new();
+		for embedded_pull_zone in storage_zone.pull_zones.unwrap_or_default().iter() {
+			// The pull zones embedded in the storage zone are not guaranteed to include every hostname
+			linked_pull_zones.push(self.get_pull_zone(embedded_pull_zone.id, None).await?);
+		}
+		if linked_pull_zones.is_empty() {
+			return Err(Error::new_from_message(&format!("No Pull Zone Linked To Storage Zone {}", self.config.storage_zone_name)));
+		}
+		return Ok(linked_pull_zones);
+	}
+
+	/// The base URL used for public URLs. The pull zone is only looked up the first time
+	pub async fn public_url_base(&self) -> Result<String, Error> {
+		if let Some(cached_public_url_base) = self.public_url_base.lock().ok().and_then(|cached_public_url_base| cached_public_url_base.clone()) {
+			return Ok(cached_public_url_base);
+		}
+		let linked_pull_zones = self.find_linked_pull_zones().await?;
+		// Prefer an enabled pull zone, but a disabled one still has the right hostnames
+		let linked_pull_zone = linked_pull_zones.iter()
+			.find(|pull_zone| pull_zone.enabled)
+			.unwrap_or(&linked_pull_zones[0]);
+		let public_url_base = select_public_url_base(&linked_pull_zone.hostnames)
+			.ok_or_else(|| Error::new_from_message(&format!("No Hostname For Pull Zone {}", linked_pull_zone.name)))?;
+		self.set_public_url_base(Some(&public_url_base));
+		return Ok(public_url_base);
+	}
+
+	/// The URL a file is served from by the pull zone linked to the storage zone
+	///
+	/// Parameters:
+	/// * remote_filepath: The filepath on bunnystorage relative to the root
+	pub async fn public_url(&self, remote_filepath: &str) -> Result<String, Error> {
+		let used_remote_filepath = RemotePath::file(remote_filepath)?;
+		let public_url_base = self.public_url_base().await?;
+		return Ok(format!("{}/{}", public_url_base, used_remote_filepath.to_url_path()));
+	}
+
+}
+
+#[cfg(test)]
+mod public_url_tests {
+	use crate::client::BunnyCDNClientConfig;
+	use super::*;
+
+	fn new_test_hostname(value: &str, is_system_hostname: bool, has_certificate: bool) -> Hostname {
+		return Hostname{
+			id: 1,
+			value: value.to_string(),
+			force_ssl: false,
+			is_system_hostname,
+			has_certificate,
+			certificate: None,
+			certificate_key: None,
+		};
+	}
+
+	#[tokio::test]
+	async fn test_public_url() {
+		let system_hostname = new_test_hostname("myzone.b-cdn.net", true, false);
+		assert_eq!(select_public_url_base(std::slice::from_ref(&system_hostname)).as_deref(), Some("https://myzone.b-cdn.net"));
+		let hostnames = vec![
+			system_hostname,
+			new_test_hostname("plain.example.com", false, false),
+			new_test_hostname("cdn.example.com", false, true),
+		];
+		assert_eq!(select_public_url_base(&hostnames).as_deref(), Some("https://cdn.example.com"));
+		assert_eq!(select_public_url_base(&hostnames[..2]).as_deref(), Some("http://plain.example.com"));
+		assert!(select_public_url_base(&[]).is_none());
+		let client = BunnyCDNClient::new(BunnyCDNClientConfig::new_offline()).unwrap();
+		client.set_public_url_base(Some("https://cdn.example.com/"));
+		assert!(client.public_url("/images/my logo#1.png").await.is_ok_and(|public_url| public_url == "https://cdn.example.com/images/my%20logo%231.png"));
+		assert!(client.public_url("images/").await.is_err());
+	}
+
+	#[tokio::test]
+	async fn test_resolve_public_url() {
+		let client_result: Result<BunnyCDNClient, Error> = BunnyCDNClient::new_from_env();
+		assert!(client_result.is_ok());
+		let client = client_result.unwrap();
+		let public_url_result = client.public_url("tests/test.txt").await;
+		assert!(public_url_result.is_ok());
+		assert!(public_url_result.unwrap().ends_with("/tests/test.txt"));
+	}
+}
@@ -0,0 +1,301 @@
+use std::{collections::{BTreeMap, BTreeSet}, sync::{atomic::{AtomicUsize, Ordering}, Mutex}};
+
+use futures::{stream, StreamExt};
+use reqwest::Url;
+
+use crate::{errors::Error, remotepath::RemotePath};
+
+use super::{publicurl::select_public_url_base, BunnyCDNClient, BUNNY_STORAGE_API_ROOT};
+
+const DEFAULT_PURGE_WILDCARD_THRESHOLD: usize = 20;
+const DEFAULT_PURGE_CONCURRENCY: usize = 8;
+
+pub struct PurgeParameters {
+	// When a batch changes at least this many files in the same directory, the directory is purged
+	// with a single wildcard instead of purging each file
+	pub wildcard_threshold: usize,
+	// The number of purge requests sent at the same time
+	pub concurrency: usize,
+}
+
+impl Default for PurgeParameters {
+
+	fn default() -> Self {
+		return PurgeParameters{
+			wildcard_threshold: DEFAULT_PURGE_WILDCARD_THRESHOLD,
+			concurrency: DEFAULT_PURGE_CONCURRENCY,
+		};
+	}
+}
+
+#[derive(Debug)]
+pub struct PurgeFailure {
+	pub url: String,
+	pub error: Error,
+}
+
+#[derive(Debug, Default)]
+pub struct PurgeReport {
+	// The purged URLs. Wildcards end with *
+	pub purged: Vec<String>,
+	pub failed: Vec<PurgeFailure>,
+}
+
+impl PurgeReport {
+
+	pub fn is_complete(&self) -> bool {
+		return self.failed.is_empty();
+	}
+}
+
+pub(crate) struct Purger {
+	params: PurgeParameters,
+	// The base URL of every linked pull zone, which are looked up once
+	pull_zone_url_bases: Mutex<Option<Vec<String>>>,
+	batch_depth: AtomicUsize,
+	pending_paths: Mutex<BTreeSet<RemotePath>>,
+}
+
+/// Changes to the same client made while a batch is open are purged together once the batch is finished.
+/// Dropping the batch without finishing it leaves the changes queued until the next purge
+pub struct PurgeBatch<'a> {
+	client: &'a BunnyCDNClient,
+	finished: bool,
+}
+
+impl PurgeBatch<'_> {
+
+	/// Closes the batch and purges the queued changes, unless an outer batch is still open
+	pub async fn finish(mut self) -> Result<PurgeReport, Error> {
+		self.finished = true;
+		self.client.end_purge_batch();
+		return self.client.purge_pending_paths().await;
+	}
+
+	/// Finishes the batch of a bulk operation, failing if any purge failed
+	pub(crate) async fn finish_checked(self) -> Result<(), Error> {
+		let purge_report = self.finish().await?;
+		if let Some(purge_failure) = purge_report.failed.first() {
+			return Err(Error::new_from_message(&format!(
+				"Failed Purging {} URLs, e.g. {} - Error: {}. The changes have been made",
+				purge_report.failed.len(),
+				purge_failure.url,
+				purge_failure.error,
+			)));
+		}
+		return Ok(());
+	}
+}
+
+impl Drop for PurgeBatch<'_> {
+
+	fn drop(&mut self) {
+		if !self.finished {
+			self.client.end_purge_batch();
+		}
+	}
+}
+
+/// The URL paths to purge for the changed paths, relative to the base URL of a pull zone.
+/// Directories and directories with at least wildcard_threshold changed files become wildcards
+pub(crate) fn collapse_purge_paths(changed_paths: &BTreeSet<RemotePath>, wildcard_threshold: usize) -> Vec<String> {
+	let wildcard_directories: BTreeSet<RemotePath> = changed_paths.iter()
+		.filter(|changed_path| changed_path.is_directory())
+		.cloned()
+		.collect();
+	let mut changed_files_by_directory: BTreeMap<RemotePath, Vec<&RemotePath>> = BTreeMap::new();
+	for changed_file in changed_paths.iter().filter(|changed_path| !changed_path.is_directory()) {
+		if let Some(parent_directory) = changed_file.parent() {
+			changed_files_by_directory.entry(parent_directory).or_default().push(changed_file);
+		}
+	}
+	let mut purge_paths: Vec<String> = Vec::new();
+	for (directory, changed_files) in changed_files_by_directory.into_iter() {
+		if wildcard_directories.iter().any(|wildcard_directory| directory.starts_with(wildcard_directory)) {
+			continue;
+		}
+		if changed_files.len() >= wildcard_threshold.max(1) {
+			purge_paths.push(format!("{}*", directory.to_url_path()));
+			continue;
+		}
+		purge_paths.extend(changed_files.iter().map(|changed_file| changed_file.to_url_path()));
+	}
+	for wildcard_directory in wildcard_directories.iter() {
+		let is_nested = wildcard_directories.iter()
+			.any(|other_directory| other_directory != wildcard_directory && wildcard_directory.starts_with(other_directory));
+		if !is_nested {
+			purge_paths.push(format!("{}*", wildcard_directory.to_url_path()));
+		}
+	}
+	return purge_paths;
+}
+
+impl BunnyCDNClient {
+
+	/// Purges the CDN cache of every pull zone linked to the storage zone after each upload and delete
+	/// made through this client, so overwritten and deleted files are not served from the cache.
+	/// Deleting a directory purges everything below it. Passing None disables purging.
+	///
+	/// When a purge fails after a successful upload or delete, the error is returned anyway,
+	/// even though the change itself has been made
+	pub fn set_purge_on_change(&mut self, params: Option<PurgeParameters>) {
+		self.purger = params.map(|params| Purger{
+			params,
+			pull_zone_url_bases: Mutex::new(None),
+			batch_depth: AtomicUsize::new(0),
+			pending_paths: Mutex::new(BTreeSet::new()),
+		});
+	}
+
+	/// Queues the purges of the following changes until the batch is finished.
+	/// Bulk operations such as copy_directory open a batch themselves
+	pub fn begin_purge_batch(&self) -> PurgeBatch<'_> {
+		if let Some(purger) = &self.purger {
+			purger.batch_depth.fetch_add(1, Ordering::SeqCst);
+		}
+		return PurgeBatch{
+			client: self,
+			finished: false,
+		};
+	}
+
+	fn end_purge_batch(&self) {
+		if let Some(purger) = &self.purger {
+			_ = purger.batch_depth.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |batch_depth| batch_depth.checked_sub(1));
+		}
+	}
+
+	async fn get_pull_zone_url_bases(&self, purger: &Purger) -> Result<Vec<String>, Error> {
+		if let Some(pull_zone_url_bases) = purger.pull_zone_url_bases.lock().ok().and_then(|pull_zone_url_bases| pull_zone_url_bases.clone()) {
+			return Ok(pull_zone_url_bases);
+		}
+		let linked_pull_zones = self.find_linked_pull_zones().await?;
+		let pull_zone_url_bases: Vec<String> = linked_pull_zones.iter()
+			.filter_map(|pull_zone| select_public_url_base(&pull_zone.hostnames))
+			.collect();
+		if let Ok(mut cached_pull_zone_url_bases) = purger.pull_zone_url_bases.lock() {
+			*cached_pull_zone_url_bases = Some(pull_zone_url_bases.clone());
+		}
+		return Ok(pull_zone_url_bases);
+	}
+
+	async fn purge_url(&self, purge_url: &str) -> Result<(), Error> {
+		let mut purge_request_url = Url::parse(&format!("{}/purge", BUNNY_STORAGE_API_ROOT))
+			.map_err(|parse_error| Error::new_from_message(&parse_error.to_string()))?;
+		purge_request_url.query_pairs_mut()
+			.append_pair("url", purge_url)
+			.append_pair("async", "false");
+		self.post(purge_request_url.as_str(), &self.config.api_key, "", None).await?;
+		return Ok(());
+	}
+
+	/*
+		Records a changed file or directory and purges it right away, unless a batch is open.
+		Does nothing if purging is disabled.
+		Parameters:
+			changed_path: The uploaded or deleted file or directory
+	*/
+	pub(crate) async fn purge_changed_path(&self, changed_path: &RemotePath) -> Result<(), Error> {
+		let Some(purger) = &self.purger else {
+			return Ok(());
+		};
+		if let Ok(mut pending_paths) = purger.pending_paths.lock() {
+			pending_paths.insert(changed_path.clone());
+		}
+		let purge_report = self.purge_pending_paths().await?;
+		if let Some(purge_failure) = purge_report.failed.first() {
+			return Err(Error::new_from_message(&format!(
+				"Failed Purging {} - Error: {}. The change to {} has been made",
+				purge_failure.url,
+				purge_failure.error,
+				changed_path,
+			)));
+		}
+		return Ok(());
+	}
+
+	async fn purge_pending_paths(&self) -> Result<PurgeReport, Error> {
+		let Some(purger) = &self.purger else {
+			return Ok(PurgeReport::default());
+		};
+		if purger.batch_depth.load(Ordering::SeqCst) > 0 {
+			return Ok(PurgeReport::default());
+		}
+		let has_pending_paths = purger.pending_paths.lock().is_ok_and(|pending_paths| !pending_paths.is_empty());
+		if !has_pending_paths {
+			return Ok(PurgeReport::default());
+		}
+		// The pending paths are only taken once the pull zones are known, so a failed lookup keeps them for the next purge
+		let pull_zone_url_bases = self.get_pull_zone_url_bases(purger).await?;
+		let pending_paths = match purger.pending_paths.lock() {
+			Ok(mut pending_paths) => std::mem::take(&mut *pending_paths),
+			Err(_) => return Ok(PurgeReport::default()),
+		};
+		if pending_paths.is_empty() {
+			return Ok(PurgeReport::default());
+		}
+		let purge_paths = collapse_purge_paths(&pending_paths, purger.params.wildcard_threshold);
+		let purge_urls: Vec<String> = pull_zone_url_bases.iter()
+			.flat_map(|pull_zone_url_base| purge_paths.iter().map(move |purge_path| format!("{}/{}", pull_zone_url_base, purge_path)))
+			.collect();
+		let purge_results: Vec<(String, Result<(), Error>)> = stream::iter(purge_urls)
+			.map(|purge_url| async move {
+				let purge_result = self.purge_url(&purge_url).await;
+				return (purge_url, purge_result);
+			})
+			.buffer_unordered(purger.params.concurrency.max(1))
+			.collect()
+			.await;
+		let mut purge_report = PurgeReport::default();
+		for (purge_url, purge_result) in purge_results.into_iter() {
+			match purge_result {
+				Ok(_) => purge_report.purged.push(purge_url),
+				Err(purge_error) => purge_report.failed.push(PurgeFailure{
+					url: purge_url,
+					error: purge_error,
+				}),
+			}
+		}
+		purge_report.purged.sort();
+		return Ok(purge_report);
+	}
+
+}
+
+#[cfg(test)]
+mod purge_tests {
+	use crate::client::BunnyCDNClientConfig;
+	use super::*;
+
+	#[test]
+	fn test_collapse_purge_paths() {
+		let changed_paths: BTreeSet<RemotePath> = [
+			RemotePath::file("app.js").unwrap(),
+			RemotePath::file("images/a b.png").unwrap(),
+			RemotePath::file("images/c.png").unwrap(),
+			RemotePath::file("images/d.png").unwrap(),
+			RemotePath::file("old/nested/e.png").unwrap(),
+			RemotePath::directory("old").unwrap(),
+			RemotePath::directory("old/nested").unwrap(),
+		].into_iter().collect();
+		assert_eq!(collapse_purge_paths(&changed_paths, 3), vec!["app.js", "images/*", "old/*"]);
+		assert_eq!(collapse_purge_paths(&changed_paths, 20), vec!["app.js", "images/a%20b.png", "images/c.png", "images/d.png", "old/*"]);
+	}
+
+	#[tokio::test]
+	async fn test_purge_batch() {
+		let mut client = BunnyCDNClient::new(BunnyCDNClientConfig::new_offline()).unwrap();
+		// Purging is disabled by default
+		assert!(client.purge_changed_path(&RemotePath::file("app.js").unwrap()).await.is_ok());
+		client.set_purge_on_change(Some(PurgeParameters::default()));
+		let purge_batch = client.begin_purge_batch();
+		let nested_purge_batch = client.begin_purge_batch();
+		assert!(client.purge_changed_path(&RemotePath::file("app.js").unwrap()).await.is_ok());
+		drop(nested_purge_batch);
+		let purger = client.purger.as_ref().unwrap();
+		assert_eq!(purger.batch_depth.load(Ordering::SeqCst), 1);
+		assert_eq!(purger.pending_paths.lock().unwrap().len(), 1);
+		drop(purge_batch);
+		assert_eq!(purger.batch_depth.load(Ordering::SeqCst), 0);
+	}
+}
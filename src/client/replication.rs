@@ -0,0 +1,247 @@
+use std::{collections::HashMap, time::{Duration, Instant}};
+
+use chrono::{DateTime, Utc};
+
+use crate::{errors::Error, models::{file::File, storageendpoint::StorageEndpoint}, remotepath::RemotePath};
+
+use super::BunnyCDNClient;
+
+const DEFAULT_REPLICATION_TIMEOUT: Duration = Duration::from_secs(300);
+const DEFAULT_REPLICATION_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+pub struct ReplicationWaitParameters {
+	// How long to wait for the endpoints to converge before giving up
+	pub timeout: Duration,
+	// How long to wait between each check
+	pub poll_interval: Duration,
+}
+
+impl Default for ReplicationWaitParameters {
+
+	fn default() -> Self {
+		return ReplicationWaitParameters{
+			timeout: DEFAULT_REPLICATION_TIMEOUT,
+			poll_interval: DEFAULT_REPLICATION_POLL_INTERVAL,
+		};
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplicationIssueKind {
+	// The file exists on the reference endpoint but not on the replica
+	Missing,
+	// The file exists on the replica but not on the reference endpoint e.g. it has been deleted
+	Unexpected,
+	// The replica has an older version of the file
+	Outdated{
+		expected: DateTime<Utc>,
+		actual: DateTime<Utc>,
+	},
+	LengthMismatch{
+		expected: u64,
+		actual: u64,
+	},
+	ChecksumMismatch{
+		expected: String,
+		actual: String,
+	},
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplicationIssue {
+	pub endpoint: StorageEndpoint,
+	pub path: String,
+	pub kind: ReplicationIssueKind,
+}
+
+#[derive(Debug)]
+pub struct ReplicationEndpointFailure {
+	pub endpoint: StorageEndpoint,
+	pub error: Error,
+}
+
+#[derive(Debug)]
+pub struct ReplicationReport {
+	pub prefix: String,
+	// The endpoint the replicas are compared against, which is the endpoint of the client
+	pub reference: StorageEndpoint,
+	// The number of files below the prefix on the reference endpoint
+	pub checked_files: usize,
+	pub issues: Vec<ReplicationIssue>,
+	// The endpoints which could not be listed
+	pub failed: Vec<ReplicationEndpointFailure>,
+}
+
+impl ReplicationReport {
+
+	/// Whether every endpoint could be listed and matches the reference endpoint
+	pub fn is_consistent(&self) -> bool {
+		return self.issues.is_empty() && self.failed.is_empty();
+	}
+
+	pub fn issues_for(&self, endpoint: StorageEndpoint) -> Vec<&ReplicationIssue> {
+		return self.issues.iter().filter(|issue| issue.endpoint == endpoint).collect();
+	}
+}
+
+/// Compares the files of a replica against the files of the reference endpoint.
+/// Files are considered equal when their checksums match. If either checksum is missing,
+/// then the length and last changed timestamp are compared instead
+pub(crate) fn compare_replicated_files(endpoint: StorageEndpoint, reference_files: &[File], replica_files: &[File]) -> Vec<ReplicationIssue> {
+	let mut replica_files_by_path: HashMap<String, &File> = replica_files.iter()
+		.filter(|replica_file| !replica_file.is_directory())
+		.map(|replica_file| (replica_file.remote_path(), replica_file))
+		.collect();
+	let mut issues: Vec<ReplicationIssue> = Vec::new();
+	for reference_file in reference_files.iter().filter(|reference_file| !reference_file.is_directory()) {
+		let path = reference_file.remote_path();
+		let Some(replica_file) = replica_files_by_path.remove(&path) else {
+			issues.push(ReplicationIssue{ endpoint, path, kind: ReplicationIssueKind::Missing });
+			continue;
+		};
+		let kind = match (reference_file.parsed_checksum(), replica_file.parsed_checksum()) {
+			(Some(expected_checksum), Some(actual_checksum)) if expected_checksum == actual_checksum => None,
+			_ if replica_file.last_changed() < reference_file.last_changed() => Some(ReplicationIssueKind::Outdated{
+				expected: reference_file.last_changed(),
+				actual: replica_file.last_changed(),
+			}),
+			(Some(expected_checksum), Some(actual_checksum)) => Some(ReplicationIssueKind::ChecksumMismatch{
+				expected: expected_checksum.to_string(),
+				actual: actual_checksum.to_string(),
+			}),
+			_ if replica_file.length() != reference_file.length() => Some(ReplicationIssueKind::LengthMismatch{
+				expected: reference_file.length(),
+				actual: replica_file.length(),
+			}),
+			_ => None,
+		};
+		if let Some(kind) = kind {
+			issues.push(ReplicationIssue{ endpoint, path, kind });
+		}
+	}
+	let mut unexpected_paths: Vec<String> = replica_files_by_path.into_keys().collect();
+	unexpected_paths.sort();
+	for path in unexpected_paths.into_iter() {
+		issues.push(ReplicationIssue{ endpoint, path, kind: ReplicationIssueKind::Unexpected });
+	}
+	return issues;
+}
+
+impl BunnyCDNClient {
+
+	async fn get_endpoint_files_recursive(&self, endpoint: &StorageEndpoint, remote_directory: &RemotePath) -> Result<Vec<File>, Error> {
+		let mut files: Vec<File> = Vec::new();
+		let mut pending_directories: Vec<RemotePath> = vec![remote_directory.as_directory()];
+		while let Some(pending_directory) = pending_directories.pop() {
+			let directory_entries = self.fetch_endpoint_directory_entries(endpoint, &pending_directory).await?;
+			for directory_entry in directory_entries.into_iter() {
+				if directory_entry.is_directory() {
+					pending_directories.push(directory_entry.to_remote_path()?);
+				} else {
+					files.push(directory_entry);
+				}
+			}
+		}
+		return Ok(files);
+	}
+
+	/// Lists the prefix on the configured endpoint and on each of the provided endpoints,
+	/// and reports the files which are missing or differ on the other endpoints.
+	/// The listing cache is bypassed, since it would hide stale replicas
+	///
+	/// Parameters:
+	/// * prefix: The directory to compare, including its subdirectories
+	/// * endpoints: The endpoints of the replication regions of the storage zone
+	pub async fn check_replication(&self, prefix: &str, endpoints: &[StorageEndpoint]) -> Result<ReplicationReport, Error> {
+		let remote_directory = RemotePath::directory(prefix)?;
+		let reference = self.config.endpoint;
+		let reference_files = self.get_endpoint_files_recursive(&reference, &remote_directory).await?;
+		let mut replication_report = ReplicationReport{
+			prefix: remote_directory.to_string(),
+			reference,
+			checked_files: reference_files.len(),
+			issues: Vec::new(),
+			failed: Vec::new(),
+		};
+		for endpoint in endpoints.iter().filter(|endpoint| **endpoint != reference) {
+			match self.get_endpoint_files_recursive(endpoint, &remote_directory).await {
+				Ok(replica_files) => replication_report.issues.extend(compare_replicated_files(*endpoint, &reference_files, &replica_files)),
+				Err(list_error) => replication_report.failed.push(ReplicationEndpointFailure{
+					endpoint: *endpoint,
+					error: list_error,
+				}),
+			}
+		}
+		return Ok(replication_report);
+	}
+
+	/// Checks the replication until every endpoint matches the configured endpoint, e.g. after uploading.
+	/// Returns the consistent report or an error describing the remaining issues once the timeout is exceeded
+	///
+	/// Parameters:
+	/// * prefix: The directory to compare, including its subdirectories
+	/// * endpoints: The endpoints of the replication regions of the storage zone
+	/// * params: The timeout and poll interval. Uses the defaults if None
+	pub async fn wait_for_replication(&self, prefix: &str, endpoints: &[StorageEndpoint], params: Option<&ReplicationWaitParameters>) -> Result<ReplicationReport, Error> {
+		let default_params = ReplicationWaitParameters::default();
+		let used_params = params.unwrap_or(&default_params);
+		let started_at = Instant::now();
+		loop {
+			let replication_report = self.check_replication(prefix, endpoints).await?;
+			if replication_report.is_consistent() {
+				return Ok(replication_report);
+			}
+			if started_at.elapsed() + used_params.poll_interval > used_params.timeout {
+				return Err(Error::new_from_message(&format!(
+					"Replication Did Not Converge Within {:?} - Issues: {} Failed Endpoints: {}",
+					used_params.timeout,
+					replication_report.issues.len(),
+					replication_report.failed.len(),
+				)));
+			}
+			tokio::time::sleep(used_params.poll_interval).await;
+		}
+	}
+
+}
+
+#[cfg(test)]
+mod replication_tests {
+	use crate::models::file::TestFileBuilder;
+	use super::*;
+
+	#[test]
+	fn test_compare_replicated_files() {
+		let checksum = "A665A45920422F9D417E4867EFDC4FB8A04A1F3FFF1FA07E998E86F7F7A27AE3";
+		let other_checksum = "B665A45920422F9D417E4867EFDC4FB8A04A1F3FFF1FA07E998E86F7F7A27AE3";
+		let reference_files = vec![
+			TestFileBuilder::new("assets/same.js").length(10).last_changed("2024-03-01T00:00:00").checksum(checksum).build(),
+			TestFileBuilder::new("assets/missing.js").length(10).last_changed("2024-03-01T00:00:00").checksum(checksum).build(),
+			TestFileBuilder::new("assets/outdated.js").length(10).last_changed("2024-03-02T00:00:00").checksum(checksum).build(),
+			TestFileBuilder::new("assets/length.js").length(10).last_changed("2024-03-01T00:00:00").build(),
+		];
+		let replica_files = vec![
+			TestFileBuilder::new("assets/same.js").length(10).last_changed("2024-03-01T00:00:05").checksum(checksum).build(),
+			TestFileBuilder::new("assets/outdated.js").length(10).last_changed("2024-03-01T00:00:00").checksum(other_checksum).build(),
+			TestFileBuilder::new("assets/length.js").length(12).last_changed("2024-03-01T00:00:00").build(),
+			TestFileBuilder::new("assets/deleted.js").length(10).last_changed("2024-03-01T00:00:00").build(),
+		];
+		let issues = compare_replicated_files(StorageEndpoint::London, &reference_files, &replica_files);
+		let issue_kinds: Vec<(&str, &ReplicationIssueKind)> = issues.iter().map(|issue| (issue.path.as_str(), &issue.kind)).collect();
+		assert_eq!(issue_kinds.len(), 4);
+		assert_eq!(issue_kinds[0], ("assets/missing.js", &ReplicationIssueKind::Missing));
+		assert!(matches!(issue_kinds[1], ("assets/outdated.js", ReplicationIssueKind::Outdated{ .. })));
+		assert_eq!(issue_kinds[2], ("assets/length.js", &ReplicationIssueKind::LengthMismatch{ expected: 10, actual: 12 }));
+		assert_eq!(issue_kinds[3], ("assets/deleted.js", &ReplicationIssueKind::Unexpected));
+		assert!(issues.iter().all(|issue| issue.endpoint == StorageEndpoint::London));
+	}
+
+	#[tokio::test]
+	async fn test_check_replication() {
+		let client_result: Result<BunnyCDNClient, Error> = BunnyCDNClient::new_from_env();
+		assert!(client_result.is_ok());
+		let client = client_result.unwrap();
+		let replication_report_result = client.check_replication("tests/", &[StorageEndpoint::NewYork]).await;
+		assert!(replication_report_result.is_ok());
+	}
+}
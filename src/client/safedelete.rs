@@ -0,0 +1,233 @@
+use glob::{MatchOptions, Pattern};
+
+use crate::{errors::Error, models::file::File, remotepath::RemotePath};
+
+use super::BunnyCDNClient;
+
+const DEFAULT_SAFE_DELETE_MAX_OBJECTS: usize = 1000;
+
+const PROTECTED_PATTERN_MATCH_OPTIONS: MatchOptions = MatchOptions{
+	case_sensitive: true,
+	require_literal_separator: true,
+	require_literal_leading_dot: false,
+};
+
+pub struct SafeDeleteParameters {
+	// Glob patterns relative to the root of the storage zone, e.g. config/** or **/*.keep
+	// Files matching any of these are never deleted
+	pub protected_patterns: Vec<String>,
+	// The maximum number of files which may be deleted. If None, then there is no limit
+	pub max_objects: Option<usize>,
+	// Explicitly allow deleting more files than max_objects
+	pub allow_exceeding_max_objects: bool,
+	// Delete each file individually instead of the entire directory in a single request
+	pub per_file: bool,
+}
+
+impl Default for SafeDeleteParameters {
+
+	fn default() -> Self {
+		return SafeDeleteParameters{
+			protected_patterns: Vec::new(),
+			max_objects: Some(DEFAULT_SAFE_DELETE_MAX_OBJECTS),
+			allow_exceeding_max_objects: false,
+			per_file: false,
+		};
+	}
+}
+
+/// The files which would be removed by safe_delete_directory
+#[derive(Debug)]
+pub struct DeletePreview {
+	pub directory: String,
+	// The files which would be deleted
+	pub files: Vec<File>,
+	// The files which match a protected pattern and would be kept
+	pub protected: Vec<File>,
+}
+
+impl DeletePreview {
+
+	pub fn total_bytes(&self) -> u64 {
+		return self.files.iter().map(|file| file.length()).sum();
+	}
+}
+
+#[derive(Debug)]
+pub struct DeleteFailure {
+	pub path: String,
+	pub error: Error,
+}
+
+#[derive(Debug, Default)]
+pub struct DeleteReport {
+	// The paths of the files which were removed
+	pub removed: Vec<String>,
+	// The paths of the files which were kept since they are protected
+	pub protected: Vec<String>,
+	// The paths of the files which could not be removed including the reason
+	pub failed: Vec<DeleteFailure>,
+}
+
+impl BunnyCDNClient {
+
+	fn compile_protected_patterns(&self, protected_patterns: &[String]) -> Result<Vec<Pattern>, Error> {
+		let mut compiled_patterns: Vec<Pattern> = Vec::new();
+		for protected_pattern in protected_patterns.iter() {
+			let used_protected_pattern = protected_pattern.trim().trim_start_matches("./").trim_start_matches('/');
+			let compiled_pattern = Pattern::new(used_protected_pattern)
+				.map_err(|pattern_error| Error::new_from_message(&format!("Invalid Protected Pattern {} - Error: {}", protected_pattern, pattern_error)))?;
+			compiled_patterns.push(compiled_pattern);
+		}
+		return Ok(compiled_patterns);
+	}
+
+	fn is_protected_path(&self, remote_path: &str, protected_patterns: &[Pattern]) -> bool {
+		let used_remote_path = remote_path.trim_end_matches('/');
+		return protected_patterns.iter()
+			.any(|protected_pattern| protected_pattern.matches_with(used_remote_path, PROTECTED_PATTERN_MATCH_OPTIONS));
+	}
+
+	/// Lists every file which would be removed when deleting the directory with safe_delete_directory.
+	/// Nothing is deleted. The directory is listed on the configured endpoint, so recent uploads are included.
+	///
+	/// Parameters:
+	/// * directory_path: The directory to delete. Must have a trailing /
+	/// * params: The protected patterns are applied to split the files into deleted and kept
+	pub async fn preview_delete_directory(&self, directory_path: &str, params: &SafeDeleteParameters) -> Result<DeletePreview, Error> {
+		if !directory_path.trim().ends_with("/") {
+			return Err(Error::new_from_message(&format!("Invalid Directory Path. Missing trailing / - Provided: {}", directory_path)));
+		}
+		let remote_directory = RemotePath::directory(directory_path)?;
+		let protected_patterns = self.compile_protected_patterns(&params.protected_patterns)?;
+		let directory_files = self.get_primary_files_recursive(&remote_directory).await?;
+		let mut delete_preview = DeletePreview{
+			directory: remote_directory.to_string(),
+			files: Vec::new(),
+			protected: Vec::new(),
+		};
+		for directory_file in directory_files.into_iter() {
+			if self.is_protected_path(&directory_file.remote_path(), &protected_patterns) {
+				delete_preview.protected.push(directory_file);
+			} else {
+				delete_preview.files.push(directory_file);
+			}
+		}
+		return Ok(delete_preview);
+	}
+
+	/// A guarded alternative to delete_directory. The directory is listed first and the delete is refused if
+	/// * the directory itself is protected
+	/// * the number of files exceeds max_objects, unless allow_exceeding_max_objects is set
+	/// * it contains protected files and per_file is not set, since the single request would remove them too
+	///
+	/// With per_file each unprotected file is deleted on its own, so the report contains exactly
+	/// which files were removed. Otherwise the directory is removed in a single request.
+	///
+	/// Parameters:
+	/// * directory_path: The directory to delete. Must have a trailing /
+	/// * params: The protection rules
+	pub async fn safe_delete_directory(&self, directory_path: &str, params: &SafeDeleteParameters) -> Result<DeleteReport, Error> {
+		self.check_write_password_ok()?;
+		let protected_patterns = self.compile_protected_patterns(&params.protected_patterns)?;
+		let remote_directory = RemotePath::directory(directory_path)?;
+		if remote_directory.is_root() {
+			return Err(Error::new_from_message("Invalid Directory Path. Deleting the root of the storage zone is not allowed"));
+		}
+		if self.is_protected_path(&remote_directory.to_string(), &protected_patterns) {
+			return Err(Error::new_from_message(&format!("Refusing to delete {} - The directory is protected", remote_directory)));
+		}
+		let delete_preview = self.preview_delete_directory(directory_path, params).await?;
+		if let Some(max_objects) = params.max_objects {
+			if delete_preview.files.len() > max_objects && !params.allow_exceeding_max_objects {
+				return Err(Error::new_from_message(&format!(
+					"Refusing to delete {} - {} files exceeds the maximum of {}. Set allow_exceeding_max_objects to override",
+					remote_directory,
+					delete_preview.files.len(),
+					max_objects,
+				)));
+			}
+		}
+		let mut delete_report = DeleteReport{
+			protected: delete_preview.protected.iter().map(|protected_file| protected_file.remote_path()).collect(),
+			..Default::default()
+		};
+		if !params.per_file {
+			if !delete_preview.protected.is_empty() {
+				return Err(Error::new_from_message(&format!(
+					"Refusing to delete {} - It contains {} protected files. Use per_file to delete the remaining files",
+					remote_directory,
+					delete_preview.protected.len(),
+				)));
+			}
+			self.delete_directory(&remote_directory.to_string()).await?;
+			delete_report.removed = delete_preview.files.iter().map(|deleted_file| deleted_file.remote_path()).collect();
+			return Ok(delete_report);
+		}
+		let purge_batch = self.begin_purge_batch();
+		for deleted_file in delete_preview.files.iter() {
+			let deleted_filepath = deleted_file.remote_path();
+			let delete_file_result = self.delete_file(&deleted_filepath).await;
+			match delete_file_result {
+				Ok(_) => delete_report.removed.push(deleted_filepath),
+				Err(delete_file_error) => delete_report.failed.push(DeleteFailure{
+					path: deleted_filepath,
+					error: delete_file_error,
+				}),
+			}
+		}
+		purge_batch.finish_checked().await?;
+		return Ok(delete_report);
+	}
+
+}
+
+#[cfg(test)]
+mod safe_delete_tests {
+	use crate::client::BunnyCDNClientConfig;
+	use super::*;
+
+	#[test]
+	fn test_is_protected_path() {
+		let client = BunnyCDNClient::new(BunnyCDNClientConfig::new_offline()).unwrap();
+		let protected_patterns = client.compile_protected_patterns(&[
+			"/config/**".to_string(),
+			"**/*.keep".to_string(),
+		]).unwrap();
+		assert!(client.is_protected_path("config/app.json", &protected_patterns));
+		assert!(client.is_protected_path("config/nested/app.json", &protected_patterns));
+		assert!(client.is_protected_path("uploads/2024/.gitkeep.keep", &protected_patterns));
+		assert!(!client.is_protected_path("uploads/2024/image.jpg", &protected_patterns));
+		assert!(client.compile_protected_patterns(&["[".to_string()]).is_err());
+	}
+
+	#[tokio::test]
+	async fn test_safe_delete_directory() {
+		let client_result: Result<BunnyCDNClient, Error> = BunnyCDNClient::new_from_env();
+		assert!(client_result.is_ok());
+		let client = client_result.unwrap();
+		let test_image_file: &str = "./tests/files/source/Test_Image.jpg";
+		let test_upload_result = client.upload_file(test_image_file, Some("/tests/files/safedelete/Test_Image.jpg")).await;
+		assert!(test_upload_result.is_ok());
+		let test_upload_protected_result = client.upload_file(test_image_file, Some("/tests/files/safedelete/protected/Test_Image.jpg")).await;
+		assert!(test_upload_protected_result.is_ok());
+		let test_params = SafeDeleteParameters{
+			protected_patterns: vec!["tests/files/safedelete/protected/**".to_string()],
+			per_file: true,
+			..Default::default()
+		};
+		// Refused when over the threshold
+		let test_threshold_params = SafeDeleteParameters{
+			max_objects: Some(0),
+			..Default::default()
+		};
+		let test_threshold_result = client.safe_delete_directory("/tests/files/safedelete/", &test_threshold_params).await;
+		assert!(test_threshold_result.is_err());
+		// Protected files are kept
+		let test_delete_result = client.safe_delete_directory("/tests/files/safedelete/", &test_params).await;
+		assert!(test_delete_result.is_ok());
+		let test_delete_report = test_delete_result.unwrap();
+		assert_eq!(test_delete_report.removed, vec!["tests/files/safedelete/Test_Image.jpg".to_string()]);
+		assert_eq!(test_delete_report.protected.len(), 1);
+	}
+}
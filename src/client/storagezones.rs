@@ -143,6 +143,7 @@ impl BunnyCDNClient {
 		check_storage_zone_availability_headers.insert(CONTENT_TYPE_HEADER_NAME.to_string(), ContentType::ApplicationJson.name().to_string());
 		let check_storage_zone_availability_options = BunnyCDNDataOptions{
 			headers: Some(check_storage_zone_availability_headers),
+			content_type: None,
 		};
 		let check_storage_zone_availability_data = serde_json::to_string(&check_storage_zone_availability_map)
 			.map_err(|serialize_error| Error::new_from_message(&serialize_error.to_string()))?;
@@ -225,6 +226,7 @@ impl BunnyCDNClient {
 		add_storage_zone_headers.insert(CONTENT_TYPE_HEADER_NAME.to_string(), ContentType::ApplicationJson.name().to_string());
 		let add_storage_zone_options = BunnyCDNDataOptions{
 			headers: Some(add_storage_zone_headers),
+			content_type: None,
 		};
 		let add_storage_zone_response = self.post(
 			&add_storage_zone_url,
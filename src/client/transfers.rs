@@ -0,0 +1,432 @@
+use std::{collections::{BTreeMap, HashMap}, sync::{Arc, Mutex}, time::Duration};
+
+use futures::StreamExt;
+use reqwest::Body;
+use sha2::{Digest, Sha256};
+
+use crate::{errors::Error, models::file::{File, FileChecksum}, remotepath::RemotePath};
+
+use super::{BunnyCDNClient, BunnyCDNDataOptions, ContentType, CONTENT_TYPE_HEADER_NAME};
+
+const CHECKSUM_HEADER_NAME: &str = "Checksum";
+// Bunnystorage can take a moment to list the checksum of a fresh upload
+const COPY_VERIFY_ATTEMPTS: u32 = 5;
+const COPY_VERIFY_INITIAL_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+#[derive(Debug)]
+pub struct TransferFailure {
+	// The source path relative to the root of the source storage zone
+	pub path: String,
+	pub error: Error,
+}
+
+/// The outcome of a directory copy or move. Since every file is transferred
+/// individually it is possible for some files to fail while others succeed
+#[derive(Debug, Default)]
+pub struct TransferReport {
+	// The source paths which were transferred successfully
+	pub transferred: Vec<String>,
+	// The source paths which failed including the reason
+	pub failed: Vec<TransferFailure>,
+}
+
+impl TransferReport {
+
+	pub fn is_complete(&self) -> bool {
+		return self.failed.is_empty();
+	}
+}
+
+/// A file which has been streamed into the target, but is not verified yet
+struct CopiedFile<'a> {
+	source_file: &'a File,
+	target_filepath: RemotePath,
+	// The checksum computed while streaming the source
+	streamed_checksum: FileChecksum,
+}
+
+enum CopyVerification {
+	Verified,
+	// Bunnystorage has not listed the target or its checksum yet
+	Pending(Error),
+	Failed(Error),
+}
+
+impl CopiedFile<'_> {
+
+	fn verify(&self, target_file_opt: Option<&File>) -> CopyVerification {
+		let Some(target_file) = target_file_opt else {
+			return CopyVerification::Pending(Error::new_from_message(&format!("Failed Verifying Copy - Target {} not found", self.target_filepath)));
+		};
+		if target_file.length() != self.source_file.length() {
+			return CopyVerification::Failed(Error::new_from_message(&format!(
+				"Failed Verifying Copy - Length Mismatch. Expected {}, Received {}",
+				self.source_file.length(),
+				target_file.length(),
+			)));
+		}
+		let Some(target_checksum) = target_file.parsed_checksum() else {
+			return CopyVerification::Pending(Error::new_from_message(&format!("Failed Verifying Copy - No checksum available for target {}", self.target_filepath)));
+		};
+		if target_checksum != self.streamed_checksum {
+			return CopyVerification::Failed(Error::new_from_message(&format!(
+				"Failed Verifying Copy - Checksum Mismatch. Expected {}, Received {}",
+				self.streamed_checksum,
+				target_checksum,
+			)));
+		}
+		return CopyVerification::Verified;
+	}
+}
+
+impl BunnyCDNClient {
+
+	/// Storage zone names are unique, so clients for the same zone share their files
+	/// even when they are configured with different endpoints
+	fn is_same_storage_zone(&self, other_client: &BunnyCDNClient) -> bool {
+		return self.config.storage_zone_name.eq_ignore_ascii_case(&other_client.config.storage_zone_name);
+	}
+
+	/*
+		Streams a single file from this client into the target client and returns
+		the SHA256 checksum computed while streaming. The copy is not verified.
+		Parameters:
+			source_file: The listing entry of the file to copy
+			target_filepath: The filepath on the target storage zone
+			target_client: The client of the target storage zone
+	*/
+	async fn stream_copy_file<'a>(&self, source_file: &'a File, target_filepath: &RemotePath, target_client: &BunnyCDNClient) -> Result<CopiedFile<'a>, Error> {
+		target_client.check_write_password_ok()?;
+		let source_filepath = source_file.to_remote_path()?;
+		if self.is_same_storage_zone(target_client) && source_filepath == *target_filepath {
+			return Err(Error::new_from_message(&format!("Invalid Target - Source and Target are the same: {}", source_filepath)));
+		}
+		let source_response = self.open_endpoint_file_response(&self.config.endpoint, &source_filepath).await?;
+		let source_hasher = Arc::new(Mutex::new(Sha256::new()));
+		let stream_hasher = Arc::clone(&source_hasher);
+		let source_stream = source_response.bytes_stream()
+			.map(move |source_chunk_result| {
+				if let Ok(source_chunk) = &source_chunk_result {
+					if let Ok(mut hasher) = stream_hasher.lock() {
+						hasher.update(source_chunk);
+					}
+				}
+				return source_chunk_result;
+			});
+
+		let mut copy_file_headers = HashMap::<String, String>::new();
+		let mut content_type = source_file.content_type().to_string();
+		if content_type.is_empty() {
+			content_type = ContentType::ApplicationOctetStream.name().to_string();
+		}
+		copy_file_headers.insert(CONTENT_TYPE_HEADER_NAME.to_string(), content_type);
+		// When the checksum is known up front, then Bunnystorage will reject a corrupted upload itself
+		if let Some(source_checksum) = source_file.checksum() {
+			copy_file_headers.insert(CHECKSUM_HEADER_NAME.to_string(), source_checksum.to_uppercase());
+		}
+		let copy_file_options = BunnyCDNDataOptions{
+			headers: Some(copy_file_headers),
+			content_type: None,
+		};
+		target_client.put_remote_file(
+			target_filepath,
+			Body::wrap_stream(source_stream),
+			Some(&copy_file_options),
+		).await?;
+
+		let streamed_checksum = match source_hasher.lock() {
+			Ok(hasher) => FileChecksum::Sha256(hasher.clone().finalize().into()),
+			Err(_) => return Err(Error::new_from_message("Failed Computing Checksum of Source")),
+		};
+		return Ok(CopiedFile{
+			source_file,
+			target_filepath: target_filepath.clone(),
+			streamed_checksum,
+		});
+	}
+
+	/*
+		Verifies copies in the same target directory by length and checksum using a single listing
+		of the configured endpoint, so replicas which are still catching up are not consulted.
+		Bunnystorage may list a fresh upload without its checksum, so the listing is repeated
+		until every checksum is available. A copy which cannot be verified in time fails.
+		Parameters:
+			target_directory: The directory on this storage zone holding every copied file
+			copied_files: The copies to verify
+	*/
+	async fn verify_copied_files(&self, target_directory: &RemotePath, copied_files: &[CopiedFile<'_>]) -> Result<Vec<Result<(), Error>>, Error> {
+		let mut retry_delay = COPY_VERIFY_INITIAL_RETRY_DELAY;
+		let mut verify_attempt: u32 = 1;
+		loop {
+			let target_entries = self.fetch_endpoint_directory_entries(&self.config.endpoint, target_directory).await?;
+			let target_files: HashMap<&str, &File> = target_entries.iter()
+				.filter(|target_entry| !target_entry.is_directory())
+				.map(|target_entry| (target_entry.object_name(), target_entry))
+				.collect();
+			let copy_verifications: Vec<CopyVerification> = copied_files.iter()
+				.map(|copied_file| {
+					let target_file_opt = copied_file.target_filepath.file_name()
+						.and_then(|target_filename| target_files.get(target_filename).copied());
+					return copied_file.verify(target_file_opt);
+				})
+				.collect();
+			let is_verification_pending = copy_verifications.iter()
+				.any(|copy_verification| matches!(copy_verification, CopyVerification::Pending(_)));
+			if !is_verification_pending || verify_attempt >= COPY_VERIFY_ATTEMPTS {
+				let verify_results = copy_verifications.into_iter()
+					.map(|copy_verification| match copy_verification {
+						CopyVerification::Verified => Ok(()),
+						CopyVerification::Pending(verify_error) | CopyVerification::Failed(verify_error) => Err(verify_error),
+					})
+					.collect();
+				return Ok(verify_results);
+			}
+			tokio::time::sleep(retry_delay).await;
+			retry_delay *= 2;
+			verify_attempt += 1;
+		}
+	}
+
+	/*
+		Streams a single file from this client into the target client and verifies
+		the result by comparing the SHA256 checksum computed while streaming with
+		the checksum reported by Bunnystorage for the uploaded file.
+		Parameters:
+			source_file: The listing entry of the file to copy
+			target_filepath: The filepath on the target storage zone
+			target_client: The client of the target storage zone
+	*/
+	pub(crate) async fn handle_copy_file(&self, source_file: &File, target_filepath: &RemotePath, target_client: &BunnyCDNClient) -> Result<(), Error> {
+		let copied_file = self.stream_copy_file(source_file, target_filepath, target_client).await?;
+		let target_directory = target_filepath.parent()
+			.ok_or_else(|| Error::new_from_message(&format!("Invalid Target - {} has no parent directory", target_filepath)))?;
+		let verify_results = target_client.verify_copied_files(&target_directory, std::slice::from_ref(&copied_file)).await?;
+		return verify_results.into_iter().next().unwrap_or(Ok(()));
+	}
+
+	pub(crate) async fn find_source_file(&self, source_filepath: &RemotePath) -> Result<File, Error> {
+		let source_file_opt = self.find_primary_entry(source_filepath).await?;
+		return match source_file_opt {
+			Some(source_file) if source_file.is_directory() => Err(Error::new_from_message(&format!("Invalid Source - {} is a directory", source_filepath))),
+			Some(source_file) => Ok(source_file),
+			None => Err(Error::new_from_message(&format!("Invalid Source - {} does not exist", source_filepath))),
+		};
+	}
+
+	/// Copies a single file on Bunnystorage. Bunnystorage has no server side copy, so the
+	/// content is streamed from the source into the target without being buffered locally.
+	/// The copy is verified by checksum once the upload has finished.
+	///
+	/// Parameters:
+	/// * source_filepath: The filepath on this storage zone
+	/// * target_filepath: The filepath on the target storage zone
+	/// * target_client: Client for another storage zone. If not provided, then the copy is made within this storage zone
+	pub async fn copy_file(&self, source_filepath: &str, target_filepath: &str, target_client: Option<&BunnyCDNClient>) -> Result<(), Error> {
+		let used_source_filepath = RemotePath::file(source_filepath)?;
+		let used_target_filepath = RemotePath::file(target_filepath)?;
+		let source_file = self.find_source_file(&used_source_filepath).await?;
+		let used_target_client = target_client.unwrap_or(self);
+		return self.handle_copy_file(&source_file, &used_target_filepath, used_target_client).await;
+	}
+
+	/// Moves a single file on Bunnystorage by copying it and deleting the source
+	/// The source is only deleted once the copy has been verified.
+	///
+	/// Parameters:
+	/// * source_filepath: The filepath on this storage zone
+	/// * target_filepath: The filepath on the target storage zone
+	/// * target_client: Client for another storage zone. If not provided, then the file is moved within this storage zone
+	pub async fn move_file(&self, source_filepath: &str, target_filepath: &str, target_client: Option<&BunnyCDNClient>) -> Result<(), Error> {
+		self.check_write_password_ok()?;
+		self.copy_file(source_filepath, target_filepath, target_client).await?;
+		return self.delete_file(source_filepath).await;
+	}
+
+	fn derive_transfer_target(&self, source_file: &File, source_directory: &RemotePath, target_directory: &RemotePath) -> Result<RemotePath, Error> {
+		let source_filepath = source_file.to_remote_path()?;
+		let relative_filepath = source_filepath
+			.strip_prefix(source_directory)
+			.unwrap_or(source_file.object_name().to_string());
+		return target_directory.join(&relative_filepath);
+	}
+
+	/// Copies every file below the source directory into the target directory, keeping
+	/// the structure of the subdirectories. A failing file does not stop the copy, instead
+	/// it is recorded in the returned report.
+	///
+	/// Parameters:
+	/// * source_directory: The directory on this storage zone
+	/// * target_directory: The directory on the target storage zone
+	/// * target_client: Client for another storage zone. If not provided, then the copy is made within this storage zone
+	pub async fn copy_directory(&self, source_directory: &str, target_directory: &str, target_client: Option<&BunnyCDNClient>) -> Result<TransferReport, Error> {
+		let used_target_client = target_client.unwrap_or(self);
+		used_target_client.check_write_password_ok()?;
+		let used_source_directory = RemotePath::directory(source_directory)?;
+		let used_target_directory = RemotePath::directory(target_directory)?;
+		let source_files = self.get_files_recursive(&used_source_directory.to_string()).await?;
+		let purge_batch = used_target_client.begin_purge_batch();
+		let mut transfer_report = TransferReport::default();
+		// Copies are verified per target directory, so each directory is only listed once
+		let mut source_files_by_target_directory: BTreeMap<RemotePath, Vec<(&File, RemotePath)>> = BTreeMap::new();
+		for source_file in source_files.iter() {
+			let target_filepath_result = self.derive_transfer_target(source_file, &used_source_directory, &used_target_directory)
+				.and_then(|target_filepath| match target_filepath.parent() {
+					Some(target_file_directory) => Ok((target_file_directory, target_filepath)),
+					None => Err(Error::new_from_message(&format!("Invalid Target - {} has no parent directory", target_filepath))),
+				});
+			match target_filepath_result {
+				Ok((target_file_directory, target_filepath)) => source_files_by_target_directory.entry(target_file_directory)
+					.or_default()
+					.push((source_file, target_filepath)),
+				Err(derive_target_error) => transfer_report.failed.push(TransferFailure{
+					path: source_file.remote_path(),
+					error: derive_target_error,
+				}),
+			}
+		}
+		for (target_file_directory, directory_source_files) in source_files_by_target_directory.iter() {
+			let mut copied_files: Vec<CopiedFile> = Vec::new();
+			for (source_file, target_filepath) in directory_source_files.iter() {
+				match self.stream_copy_file(source_file, target_filepath, used_target_client).await {
+					Ok(copied_file) => copied_files.push(copied_file),
+					Err(copy_file_error) => transfer_report.failed.push(TransferFailure{
+						path: source_file.remote_path(),
+						error: copy_file_error,
+					}),
+				}
+			}
+			if copied_files.is_empty() {
+				continue;
+			}
+			let verify_results = match used_target_client.verify_copied_files(target_file_directory, &copied_files).await {
+				Ok(verify_results) => verify_results,
+				Err(verify_error) => copied_files.iter()
+					.map(|_| Err(Error::new_from_message(&format!("Failed Verifying Copy - Error: {}", verify_error))))
+					.collect(),
+			};
+			for (copied_file, verify_result) in copied_files.iter().zip(verify_results) {
+				let source_filepath = copied_file.source_file.remote_path();
+				match verify_result {
+					Ok(_) => transfer_report.transferred.push(source_filepath),
+					Err(verify_error) => transfer_report.failed.push(TransferFailure{
+						path: source_filepath,
+						error: verify_error,
+					}),
+				}
+			}
+		}
+		purge_batch.finish_checked().await?;
+		return Ok(transfer_report);
+	}
+
+	/// Moves every file below the source directory into the target directory.
+	/// Each source file is deleted once its copy has been verified, so failed files remain in place.
+	/// Only the moved files are deleted, the source directories themselves are kept.
+	/// Within the same storage zone the target must not be the source directory or inside of it.
+	///
+	/// Parameters:
+	/// * source_directory: The directory on this storage zone
+	/// * target_directory: The directory on the target storage zone
+	/// * target_client: Client for another storage zone. If not provided, then the directory is moved within this storage zone
+	pub async fn move_directory(&self, source_directory: &str, target_directory: &str, target_client: Option<&BunnyCDNClient>) -> Result<TransferReport, Error> {
+		self.check_write_password_ok()?;
+		let used_source_directory = RemotePath::directory(source_directory)?;
+		if used_source_directory.is_root() {
+			return Err(Error::new_from_message("Invalid Source Directory - Moving the root of the storage zone is not allowed"));
+		}
+		let used_target_directory = RemotePath::directory(target_directory)?;
+		let is_same_storage_zone = target_client.is_none_or(|target_client| self.is_same_storage_zone(target_client));
+		if is_same_storage_zone && used_target_directory.starts_with(&used_source_directory) {
+			return Err(Error::new_from_message(&format!(
+				"Invalid Target Directory - {} is inside of the source directory {}",
+				used_target_directory,
+				used_source_directory,
+			)));
+		}
+		let copy_report = self.copy_directory(source_directory, target_directory, target_client).await?;
+		let purge_batch = self.begin_purge_batch();
+		let mut move_report = TransferReport{
+			transferred: Vec::new(),
+			failed: copy_report.failed,
+		};
+		for copied_filepath in copy_report.transferred.into_iter() {
+			let delete_source_result = self.delete_file(&copied_filepath).await;
+			match delete_source_result {
+				Ok(_) => move_report.transferred.push(copied_filepath),
+				Err(delete_source_error) => move_report.failed.push(TransferFailure{
+					path: copied_filepath,
+					error: delete_source_error,
+				}),
+			}
+		}
+		purge_batch.finish_checked().await?;
+		return Ok(move_report);
+	}
+
+}
+
+#[cfg(test)]
+mod transfers_tests {
+	use crate::{client::BunnyCDNClientConfig, models::storageendpoint::StorageEndpoint};
+	use super::*;
+
+	#[tokio::test]
+	async fn test_reject_nested_move_target() {
+		let client_config = BunnyCDNClientConfig{
+			write_password: Some("write_password".to_string()),
+			..BunnyCDNClientConfig::new_offline()
+		};
+		let client = BunnyCDNClient::new(client_config).unwrap();
+		let other_endpoint_client = BunnyCDNClient::new(BunnyCDNClientConfig{
+			endpoint: StorageEndpoint::NewYork,
+			..BunnyCDNClientConfig::new_offline()
+		}).unwrap();
+		assert!(client.is_same_storage_zone(&other_endpoint_client));
+		assert!(client.move_directory("a/", "a/archive/", None).await.is_err());
+		assert!(client.move_directory("a/", "/a", None).await.is_err());
+		assert!(client.move_directory("a/", "a/", Some(&other_endpoint_client)).await.is_err());
+	}
+
+	#[tokio::test]
+	async fn test_copy_and_move_file() {
+		let client_result: Result<BunnyCDNClient, Error> = BunnyCDNClient::new_from_env();
+		assert!(client_result.is_ok());
+		let client = client_result.unwrap();
+		let test_image_file: &str = "./tests/files/source/Test_Image.jpg";
+		let test_source_filepath: &str = "/tests/files/transfers/source/Test_Image.jpg";
+		let test_upload_result = client.upload_file(test_image_file, Some(test_source_filepath)).await;
+		assert!(test_upload_result.is_ok());
+		// Copy keeps the source
+		let test_copy_filepath: &str = "/tests/files/transfers/copy/Test_Image.jpg";
+		let test_copy_result = client.copy_file(test_source_filepath, test_copy_filepath, None).await;
+		if let Err(test_copy_error) = &test_copy_result {
+			println!("Failed Copying File - Error: {}", test_copy_error);
+		}
+		assert!(test_copy_result.is_ok());
+		// Move removes the source
+		let test_move_filepath: &str = "/tests/files/transfers/move/Test_Image.jpg";
+		let test_move_result = client.move_file(test_source_filepath, test_move_filepath, None).await;
+		assert!(test_move_result.is_ok());
+		let test_moved_source_result = client.stat(test_source_filepath).await;
+		assert!(test_moved_source_result.is_ok_and(|moved_source| moved_source.is_none()));
+		// Copy onto itself is rejected
+		let test_copy_self_result = client.copy_file(test_copy_filepath, test_copy_filepath, None).await;
+		assert!(test_copy_self_result.is_err());
+	}
+
+	#[tokio::test]
+	async fn test_copy_directory() {
+		let client_result: Result<BunnyCDNClient, Error> = BunnyCDNClient::new_from_env();
+		assert!(client_result.is_ok());
+		let client = client_result.unwrap();
+		let test_image_file: &str = "./tests/files/source/Test_Image.jpg";
+		let test_upload_result = client.upload_file(test_image_file, Some("/tests/files/transfers/directory/nested/Test_Image.jpg")).await;
+		assert!(test_upload_result.is_ok());
+		let test_copy_directory_result = client.copy_directory(
+			"/tests/files/transfers/directory/",
+			"/tests/files/transfers/directory_copy/",
+			None,
+		).await;
+		assert!(test_copy_directory_result.is_ok_and(|copy_report| copy_report.is_complete()));
+	}
+}
@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+
+use crate::{errors::Error, models::{file::File, usagereport::{DirectoryUsage, UsageFile, UsageReport}}, remotepath::RemotePath};
+
+use super::BunnyCDNClient;
+
+const DEFAULT_USAGE_REPORT_LARGEST_FILES_COUNT: usize = 10;
+
+pub struct UsageReportParameters {
+	// The deepest level of subdirectories to report on, where 0 only reports the prefix itself.
+	// Files below this depth are still counted in their ancestors. If None, then there is no limit
+	pub max_depth: Option<usize>,
+	// The number of largest files to keep per directory
+	pub largest_files_count: usize,
+}
+
+impl Default for UsageReportParameters {
+
+	fn default() -> Self {
+		return UsageReportParameters{
+			max_depth: None,
+			largest_files_count: DEFAULT_USAGE_REPORT_LARGEST_FILES_COUNT,
+		};
+	}
+}
+
+fn new_directory_usage(remote_directory: &RemotePath, depth: usize) -> DirectoryUsage {
+	return DirectoryUsage{
+		path: remote_directory.to_string(),
+		depth,
+		total_bytes: 0,
+		file_count: 0,
+		largest_files: Vec::new(),
+		oldest_last_changed: None,
+		newest_last_changed: None,
+	};
+}
+
+fn add_file_usage(directory_usage: &mut DirectoryUsage, file: &File, remote_filepath: &RemotePath) {
+	directory_usage.total_bytes += file.length();
+	directory_usage.file_count += 1;
+	directory_usage.largest_files.push(UsageFile{
+		path: remote_filepath.to_string(),
+		length: file.length(),
+	});
+	let last_changed = file.last_changed();
+	if directory_usage.oldest_last_changed.is_none_or(|oldest_last_changed| last_changed < oldest_last_changed) {
+		directory_usage.oldest_last_changed = Some(last_changed);
+	}
+	if directory_usage.newest_last_changed.is_none_or(|newest_last_changed| last_changed > newest_last_changed) {
+		directory_usage.newest_last_changed = Some(last_changed);
+	}
+}
+
+/// Aggregates the files per directory below the prefix. Every file is counted in each of
+/// its ancestor directories down to the maximum depth.
+pub(crate) fn build_usage_report(prefix: &RemotePath, files: &[File], params: &UsageReportParameters) -> Result<UsageReport, Error> {
+	let prefix_length = prefix.segments().len();
+	let mut directory_usages: BTreeMap<RemotePath, DirectoryUsage> = BTreeMap::new();
+	directory_usages.insert(prefix.clone(), new_directory_usage(prefix, 0));
+	for file in files.iter().filter(|file| !file.is_directory()) {
+		let remote_filepath = file.to_remote_path()?;
+		if !remote_filepath.starts_with(prefix) {
+			continue;
+		}
+		let relative_segments = &remote_filepath.segments()[prefix_length..];
+		// The file itself is the last segment, so the remaining segments are its directories
+		let file_depth = relative_segments.len().saturating_sub(1);
+		let reported_depth = params.max_depth.map_or(file_depth, |max_depth| file_depth.min(max_depth));
+		for depth in 0..=reported_depth {
+			let remote_directory = RemotePath::directory(&remote_filepath.segments()[..prefix_length + depth].join("/"))?;
+			let directory_usage = directory_usages.entry(remote_directory)
+				.or_insert_with_key(|remote_directory| new_directory_usage(remote_directory, depth));
+			add_file_usage(directory_usage, file, &remote_filepath);
+		}
+	}
+	let mut directories: Vec<DirectoryUsage> = Vec::with_capacity(directory_usages.len());
+	for (_, mut directory_usage) in directory_usages.into_iter() {
+		directory_usage.largest_files.sort_by(|usage_file, other_usage_file| other_usage_file.length.cmp(&usage_file.length)
+			.then_with(|| usage_file.path.cmp(&other_usage_file.path)));
+		directory_usage.largest_files.truncate(params.largest_files_count);
+		directories.push(directory_usage);
+	}
+	return Ok(UsageReport{
+		prefix: prefix.to_string(),
+		generated_at: Utc::now(),
+		directories,
+	});
+}
+
+impl BunnyCDNClient {
+
+	/// Walks every file below the prefix and reports the storage used per directory,
+	/// including the file count, largest files and the oldest and newest changes.
+	/// The report can be exported with UsageReport::to_json or UsageReport::to_csv
+	///
+	/// Parameters:
+	/// * prefix: The directory to report on. An empty prefix or / reports on the entire storage zone
+	/// * params: The depth and number of largest files to report
+	pub async fn get_usage_report(&self, prefix: &str, params: &UsageReportParameters) -> Result<UsageReport, Error> {
+		let remote_prefix = RemotePath::directory(prefix)?;
+		let prefix_files = self.get_files_recursive(&remote_prefix.to_string()).await?;
+		return build_usage_report(&remote_prefix, &prefix_files, params);
+	}
+
+}
+
+#[cfg(test)]
+mod usage_tests {
+	use crate::models::file::TestFileBuilder;
+	use super::*;
+
+	#[test]
+	fn test_build_usage_report() {
+		let test_files = vec![
+			TestFileBuilder::new("customers/index.json").length(10).last_changed("2024-01-01T00:00:00").date_created("2024-01-01T00:00:00").build(),
+			TestFileBuilder::new("customers/acme/logo.png").length(300).last_changed("2024-02-01T00:00:00").date_created("2024-02-01T00:00:00").build(),
+			TestFileBuilder::new("customers/acme/invoices/2024/january.pdf").length(500).last_changed("2024-03-01T00:00:00").date_created("2024-03-01T00:00:00").build(),
+			TestFileBuilder::new("customers/globex/logo.png").length(200).last_changed("2023-12-01T00:00:00").date_created("2023-12-01T00:00:00").build(),
+		];
+		let test_params = UsageReportParameters{
+			max_depth: Some(1),
+			largest_files_count: 2,
+		};
+		let test_prefix = RemotePath::directory("customers").unwrap();
+		let usage_report = build_usage_report(&test_prefix, &test_files, &test_params).unwrap();
+		let directory_paths: Vec<&str> = usage_report.directories.iter().map(|directory_usage| directory_usage.path.as_str()).collect();
+		assert_eq!(directory_paths, vec!["customers/", "customers/acme/", "customers/globex/"]);
+		let prefix_usage = &usage_report.directories[0];
+		assert_eq!(prefix_usage.total_bytes, 1010);
+		assert_eq!(prefix_usage.file_count, 4);
+		assert_eq!(prefix_usage.largest_files.len(), 2);
+		assert_eq!(prefix_usage.largest_files[0].path, "customers/acme/invoices/2024/january.pdf");
+		assert_eq!(prefix_usage.oldest_last_changed.unwrap().to_rfc3339(), "2023-12-01T00:00:00+00:00");
+		// Files deeper than the maximum depth are counted in the deepest reported directory
+		let acme_usage = &usage_report.directories[1];
+		assert_eq!(acme_usage.total_bytes, 800);
+		assert_eq!(acme_usage.depth, 1);
+		let usage_csv = usage_report.to_csv().unwrap();
+		assert!(usage_csv.starts_with("path,depth,total_bytes,file_count"));
+		assert_eq!(usage_csv.lines().count(), 4);
+		assert!(usage_report.to_json().is_ok_and(|usage_json| usage_json.contains("\"totalBytes\": 1010")));
+	}
+
+	#[tokio::test]
+	async fn test_get_usage_report() {
+		let client_result: Result<BunnyCDNClient, Error> = BunnyCDNClient::new_from_env();
+		assert!(client_result.is_ok());
+		let usage_report_result = client_result.unwrap().get_usage_report("/tests/", &UsageReportParameters::default()).await;
+		assert!(usage_report_result.is_ok());
+	}
+}
@@ -0,0 +1,213 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use crate::{errors::Error, models::file::{File, FileChecksum}, remotepath::RemotePath};
+
+use super::BunnyCDNClient;
+
+const DEFAULT_VERSIONS_PREFIX: &str = ".versions/";
+// Version IDs are timestamps which sort in the same order as they were created
+const VERSION_ID_FORMAT: &str = "%Y%m%dT%H%M%S%.6fZ";
+
+pub struct VersioningParameters {
+	// The hidden directory holding the versions. The versions of a file are kept in
+	// <versions_prefix>/<path of the file>/<version id>
+	pub versions_prefix: String,
+	// The number of versions to keep per file. Older versions are pruned after each backup.
+	// If None, then every version is kept
+	pub max_versions: Option<usize>,
+}
+
+impl Default for VersioningParameters {
+
+	fn default() -> Self {
+		return VersioningParameters{
+			versions_prefix: DEFAULT_VERSIONS_PREFIX.to_string(),
+			max_versions: None,
+		};
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct FileVersion {
+	// Identifies the version when restoring it
+	pub version_id: String,
+	// The path of the file the version belongs to
+	pub path: String,
+	// Where the version is stored
+	pub version_path: String,
+	// When the version was backed up, which is when the file was overwritten
+	pub created: DateTime<Utc>,
+	pub length: u64,
+	pub checksum: Option<FileChecksum>,
+}
+
+impl BunnyCDNClient {
+
+	/// Enables versioning for every upload made through this client. Before a file is overwritten
+	/// the existing file is copied into the versions prefix, from where it can be restored.
+	/// Passing None disables versioning, but keeps the existing versions.
+	/// Fails if the versions prefix is the root of the storage zone, since every file would count as a version
+	pub fn set_versioning(&mut self, versioning: Option<VersioningParameters>) -> Result<(), Error> {
+		if let Some(versioning) = &versioning {
+			if RemotePath::directory(&versioning.versions_prefix)?.is_root() {
+				return Err(Error::new_from_message(&format!("Invalid Versions Prefix - Provided: {}. The prefix may not be the root", versioning.versions_prefix)));
+			}
+		}
+		self.versioning = versioning;
+		return Ok(());
+	}
+
+	pub fn versioning(&self) -> Option<&VersioningParameters> {
+		return self.versioning.as_ref();
+	}
+
+	fn get_versions_root(&self) -> Result<RemotePath, Error> {
+		let versions_prefix = self.versioning.as_ref()
+			.map_or(DEFAULT_VERSIONS_PREFIX, |versioning| versioning.versions_prefix.as_str());
+		return RemotePath::directory(versions_prefix);
+	}
+
+	fn get_versions_directory(&self, remote_filepath: &RemotePath) -> Result<RemotePath, Error> {
+		return self.get_versions_root()?.join(&format!("{}/", remote_filepath));
+	}
+
+	/*
+		Copies the current version of the file into the versions prefix before it is overwritten.
+		Does nothing if versioning is disabled, the file does not exist yet or the file is a version itself.
+		Parameters:
+			remote_filepath: The filepath on bunnystorage which is about to be overwritten
+	*/
+	pub(crate) async fn backup_file_version(&self, remote_filepath: &RemotePath) -> Result<(), Error> {
+		let Some(versioning) = &self.versioning else {
+			return Ok(());
+		};
+		let versions_root = self.get_versions_root()?;
+		if remote_filepath.starts_with(&versions_root) {
+			return Ok(());
+		}
+		let existing_file = match self.find_primary_entry(remote_filepath).await? {
+			Some(existing_file) if !existing_file.is_directory() => existing_file,
+			_ => return Ok(()),
+		};
+		let version_id = Utc::now().format(VERSION_ID_FORMAT).to_string();
+		let version_filepath = self.get_versions_directory(remote_filepath)?.join(&version_id)?;
+		self.handle_copy_file(&existing_file, &version_filepath, self).await
+			.map_err(|backup_error| Error::new_from_message(&format!("Failed Backing Up {} - Error: {}", remote_filepath, backup_error)))?;
+		if let Some(max_versions) = versioning.max_versions {
+			self.prune_file_versions(&remote_filepath.to_string(), max_versions).await?;
+		}
+		return Ok(());
+	}
+
+	fn parse_file_version(&self, remote_filepath: &RemotePath, version_file: &File) -> Option<FileVersion> {
+		let version_id = version_file.object_name();
+		let created = NaiveDateTime::parse_from_str(version_id, VERSION_ID_FORMAT).ok()?.and_utc();
+		return Some(FileVersion{
+			version_id: version_id.to_string(),
+			path: remote_filepath.to_string(),
+			version_path: version_file.remote_path(),
+			created,
+			length: version_file.length(),
+			checksum: version_file.parsed_checksum(),
+		});
+	}
+
+	/// Lists the backed up versions of a file, newest first. The current file is not included
+	///
+	/// Parameters:
+	/// * remote_filepath: The filepath on bunnystorage relative to the root
+	pub async fn list_file_versions(&self, remote_filepath: &str) -> Result<Vec<FileVersion>, Error> {
+		let used_remote_filepath = RemotePath::file(remote_filepath)?;
+		let versions_directory = self.get_versions_directory(&used_remote_filepath)?;
+		let version_files = self.get_remote_directory_entries(&versions_directory).await?;
+		let mut file_versions: Vec<FileVersion> = version_files.iter()
+			.filter(|version_file| !version_file.is_directory())
+			.filter_map(|version_file| self.parse_file_version(&used_remote_filepath, version_file))
+			.collect();
+		file_versions.sort_by(|file_version, other_file_version| other_file_version.version_id.cmp(&file_version.version_id));
+		return Ok(file_versions);
+	}
+
+	/// Replaces the file with one of its versions. With versioning enabled the replaced file
+	/// is backed up first, so a restore can be undone as well
+	///
+	/// Parameters:
+	/// * remote_filepath: The filepath on bunnystorage relative to the root
+	/// * version_id: The version to restore as returned by list_file_versions
+	pub async fn restore_file_version(&self, remote_filepath: &str, version_id: &str) -> Result<(), Error> {
+		let used_remote_filepath = RemotePath::file(remote_filepath)?;
+		let version_filepath = self.get_versions_directory(&used_remote_filepath)?.join(version_id)?;
+		if version_filepath.is_directory() || version_filepath.parent() != Some(self.get_versions_directory(&used_remote_filepath)?) {
+			return Err(Error::new_from_message(&format!("Invalid Version ID - Provided: {}", version_id)));
+		}
+		let version_file = self.find_source_file(&version_filepath).await?;
+		return self.handle_copy_file(&version_file, &used_remote_filepath, self).await;
+	}
+
+	/// Deletes the oldest versions of a file, keeping the provided number of newest versions.
+	/// Returns the IDs of the deleted versions
+	///
+	/// Parameters:
+	/// * remote_filepath: The filepath on bunnystorage relative to the root
+	/// * keep_versions: The number of versions to keep
+	pub async fn prune_file_versions(&self, remote_filepath: &str, keep_versions: usize) -> Result<Vec<String>, Error> {
+		self.check_write_password_ok()?;
+		let file_versions = self.list_file_versions(remote_filepath).await?;
+		let purge_batch = self.begin_purge_batch();
+		let mut pruned_version_ids: Vec<String> = Vec::new();
+		for pruned_version in file_versions.iter().skip(keep_versions) {
+			self.delete_file(&pruned_version.version_path).await?;
+			pruned_version_ids.push(pruned_version.version_id.clone());
+		}
+		purge_batch.finish_checked().await?;
+		return Ok(pruned_version_ids);
+	}
+
+}
+
+#[cfg(test)]
+mod versioning_tests {
+	use crate::client::BunnyCDNClientConfig;
+	use super::*;
+
+	#[test]
+	fn test_get_versions_directory() {
+		let mut client = BunnyCDNClient::new(BunnyCDNClientConfig::new_offline()).unwrap();
+		let remote_filepath = RemotePath::file("deploy/app.tar.gz").unwrap();
+		assert!(client.get_versions_directory(&remote_filepath).is_ok_and(|versions_directory| versions_directory.to_string() == ".versions/deploy/app.tar.gz/"));
+		assert!(client.set_versioning(Some(VersioningParameters{
+			versions_prefix: "/_history".to_string(),
+			..Default::default()
+		})).is_ok());
+		assert!(client.get_versions_directory(&remote_filepath).is_ok_and(|versions_directory| versions_directory.to_string() == "_history/deploy/app.tar.gz/"));
+		for root_versions_prefix in ["", "/", "./"] {
+			assert!(client.set_versioning(Some(VersioningParameters{
+				versions_prefix: root_versions_prefix.to_string(),
+				..Default::default()
+			})).is_err());
+		}
+		// A rejected prefix keeps the previous versioning
+		assert!(client.versioning().is_some_and(|versioning| versioning.versions_prefix == "/_history"));
+		let version_id = Utc::now().format(VERSION_ID_FORMAT).to_string();
+		assert!(NaiveDateTime::parse_from_str(&version_id, VERSION_ID_FORMAT).is_ok());
+	}
+
+	#[tokio::test]
+	async fn test_versioned_upload() {
+		let client_result: Result<BunnyCDNClient, Error> = BunnyCDNClient::new_from_env();
+		assert!(client_result.is_ok());
+		let mut client = client_result.unwrap();
+		assert!(client.set_versioning(Some(VersioningParameters::default())).is_ok());
+		let test_remote_filepath = "tests/files/versioning/test.txt";
+		assert!(client.upload_file_content(test_remote_filepath, b"first".to_vec(), None).await.is_ok());
+		assert!(client.upload_file_content(test_remote_filepath, b"second".to_vec(), None).await.is_ok());
+		let test_versions_result = client.list_file_versions(test_remote_filepath).await;
+		assert!(test_versions_result.is_ok());
+		let test_versions = test_versions_result.unwrap();
+		assert!(!test_versions.is_empty());
+		assert!(client.restore_file_version(test_remote_filepath, &test_versions[0].version_id).await.is_ok());
+		assert!(client.download_file_content(test_remote_filepath).await.is_ok_and(|content| content == b"first"));
+		assert!(client.prune_file_versions(test_remote_filepath, 0).await.is_ok());
+		assert!(client.delete_file(test_remote_filepath).await.is_ok());
+	}
+}
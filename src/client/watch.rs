@@ -0,0 +1,489 @@
+use std::{collections::{BTreeSet, HashMap}, future::Future, path::{Component, Path, PathBuf}, time::Duration};
+
+use futures::{channel::mpsc, FutureExt, StreamExt};
+use glob::{MatchOptions, Pattern};
+use notify::{EventKind, RecursiveMode, Watcher};
+use sha2::{Digest, Sha256};
+use tokio::{fs, io::AsyncReadExt};
+
+use crate::{errors::Error, models::file::{File, FileChecksum}, remotepath::RemotePath};
+
+use super::{purge::PurgeBatch, BunnyCDNClient};
+
+const DEFAULT_WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+const DEFAULT_WATCH_MAX_DEBOUNCE: Duration = Duration::from_secs(10);
+const LOCAL_CHECKSUM_BUFFER_SIZE: usize = 64 * 1024;
+
+const IGNORE_PATTERN_MATCH_OPTIONS: MatchOptions = MatchOptions{
+	case_sensitive: true,
+	require_literal_separator: false,
+	require_literal_leading_dot: false,
+};
+
+fn map_watch_io_error(io_error: std::io::Error) -> Error {
+	return Error::new_from_message(&format!("Failed Reading Watched Directory - Error: {}", io_error));
+}
+
+pub struct WatchParameters {
+	// Changes are mirrored once no further change has happened for this long
+	pub debounce: Duration,
+	// Changes are mirrored after collecting them for this long, even if further changes keep happening e.g. to a log file
+	pub max_debounce: Duration,
+	// Uploads local files which are missing or differ remotely before watching
+	pub reconcile_on_start: bool,
+	// Deletes remote files which do not exist locally. Disabled by default, so local deletes are not mirrored
+	pub delete_remote: bool,
+	// Glob patterns relative to the local directory, e.g. **/.DS_Store. Matching files are never synced
+	pub ignore_patterns: Vec<String>,
+}
+
+impl Default for WatchParameters {
+
+	fn default() -> Self {
+		return WatchParameters{
+			debounce: DEFAULT_WATCH_DEBOUNCE,
+			max_debounce: DEFAULT_WATCH_MAX_DEBOUNCE,
+			reconcile_on_start: true,
+			delete_remote: false,
+			ignore_patterns: Vec::new(),
+		};
+	}
+}
+
+#[derive(Debug)]
+pub struct WatchFailure {
+	pub path: String,
+	pub error: Error,
+}
+
+#[derive(Debug, Default)]
+pub struct WatchReport {
+	// The remote paths of the uploaded files
+	pub uploaded: Vec<String>,
+	// The remote paths of the deleted files and directories
+	pub deleted: Vec<String>,
+	pub failed: Vec<WatchFailure>,
+}
+
+impl WatchReport {
+
+	fn merge(&mut self, other_report: WatchReport) {
+		self.uploaded.extend(other_report.uploaded);
+		self.deleted.extend(other_report.deleted);
+		self.failed.extend(other_report.failed);
+	}
+}
+
+struct WatchTarget {
+	local_directory: PathBuf,
+	remote_directory: RemotePath,
+	ignore_patterns: Vec<Pattern>,
+}
+
+impl WatchTarget {
+
+	/// The path relative to the local directory with / as separator. None if outside the local directory
+	fn relative_path(&self, local_path: &Path) -> Option<String> {
+		let relative_path = local_path.strip_prefix(&self.local_directory).ok()?;
+		let mut relative_segments: Vec<String> = Vec::new();
+		for path_component in relative_path.components() {
+			match path_component {
+				Component::Normal(segment) => relative_segments.push(segment.to_str()?.to_string()),
+				_ => return None,
+			}
+		}
+		if relative_segments.is_empty() {
+			return None;
+		}
+		return Some(relative_segments.join("/"));
+	}
+
+	fn is_ignored(&self, relative_path: &str) -> bool {
+		return self.ignore_patterns.iter()
+			.any(|ignore_pattern| ignore_pattern.matches_with(relative_path, IGNORE_PATTERN_MATCH_OPTIONS));
+	}
+
+	/// The remote file a local file is mirrored to. None for ignored files and paths outside the local directory
+	fn remote_filepath(&self, local_path: &Path) -> Option<RemotePath> {
+		let relative_path = self.relative_path(local_path)?;
+		if self.is_ignored(&relative_path) {
+			return None;
+		}
+		return self.remote_directory.join(&relative_path).ok();
+	}
+}
+
+/*
+	Collects the changed paths of a burst of events, until no event has arrived for the debounce duration
+	or the burst has been collected for max_debounce. Events after that are left for the next burst.
+	Parameters:
+		event_receiver: Receives the events of the watcher
+		first_watch_event: The event which started the burst
+		params: The debounce durations
+*/
+async fn collect_watch_burst(event_receiver: &mut mpsc::UnboundedReceiver<notify::Result<notify::Event>>, first_watch_event: notify::Result<notify::Event>, params: &WatchParameters) -> (BTreeSet<PathBuf>, Vec<notify::Error>) {
+	let burst_deadline = tokio::time::Instant::now() + params.max_debounce.max(params.debounce);
+	let mut changed_paths: BTreeSet<PathBuf> = BTreeSet::new();
+	let mut watcher_errors: Vec<notify::Error> = Vec::new();
+	let mut pending_watch_event = Some(first_watch_event);
+	while let Some(watch_event) = pending_watch_event.take() {
+		match watch_event {
+			Ok(watch_event) if !matches!(watch_event.kind, EventKind::Access(_)) => changed_paths.extend(watch_event.paths),
+			Ok(_) => {},
+			Err(watcher_error) => watcher_errors.push(watcher_error),
+		}
+		// Keep collecting until the changes have settled or the burst has reached max_debounce
+		let debounce = params.debounce.min(burst_deadline.saturating_duration_since(tokio::time::Instant::now()));
+		if debounce.is_zero() {
+			break;
+		}
+		if let Ok(Some(next_watch_event)) = tokio::time::timeout(debounce, event_receiver.next()).await {
+			pending_watch_event = Some(next_watch_event);
+		}
+	}
+	return (changed_paths, watcher_errors);
+}
+
+async fn collect_local_files(local_directory: &Path) -> Result<Vec<PathBuf>, Error> {
+	let mut local_files: Vec<PathBuf> = Vec::new();
+	let mut pending_directories: Vec<PathBuf> = vec![local_directory.to_path_buf()];
+	while let Some(pending_directory) = pending_directories.pop() {
+		let mut directory_entries = fs::read_dir(&pending_directory).await.map_err(map_watch_io_error)?;
+		while let Some(directory_entry) = directory_entries.next_entry().await.map_err(map_watch_io_error)? {
+			let entry_type = directory_entry.file_type().await.map_err(map_watch_io_error)?;
+			if entry_type.is_dir() {
+				pending_directories.push(directory_entry.path());
+			} else if entry_type.is_file() {
+				local_files.push(directory_entry.path());
+			}
+		}
+	}
+	local_files.sort();
+	return Ok(local_files);
+}
+
+async fn compute_local_checksum(local_path: &Path) -> Result<FileChecksum, Error> {
+	let mut local_file = fs::File::open(local_path).await.map_err(map_watch_io_error)?;
+	let mut local_hasher = Sha256::new();
+	let mut checksum_buffer = vec![0u8; LOCAL_CHECKSUM_BUFFER_SIZE];
+	loop {
+		let read_size = local_file.read(&mut checksum_buffer).await.map_err(map_watch_io_error)?;
+		if read_size == 0 {
+			break;
+		}
+		local_hasher.update(&checksum_buffer[..read_size]);
+	}
+	return Ok(FileChecksum::Sha256(local_hasher.finalize().into()));
+}
+
+/// Whether the local file has to be uploaded to match the remote file.
+/// Without a remote checksum, the length and modification time are compared instead
+async fn is_local_file_changed(local_path: &Path, remote_file: &File) -> Result<bool, Error> {
+	let local_metadata = fs::metadata(local_path).await.map_err(map_watch_io_error)?;
+	if local_metadata.len() != remote_file.length() {
+		return Ok(true);
+	}
+	return match remote_file.parsed_checksum() {
+		Some(remote_checksum) => Ok(compute_local_checksum(local_path).await? != remote_checksum),
+		None => {
+			let local_modified: chrono::DateTime<chrono::Utc> = local_metadata.modified().map_err(map_watch_io_error)?.into();
+			Ok(local_modified > remote_file.last_changed())
+		},
+	};
+}
+
+impl BunnyCDNClient {
+
+	fn prepare_watch_target(&self, local_directory: &str, remote_directory: &str, params: &WatchParameters) -> Result<WatchTarget, Error> {
+		let canonical_local_directory = std::fs::canonicalize(local_directory)
+			.map_err(|canonicalize_error| Error::new_from_message(&format!("Invalid Local Directory {} - Error: {}", local_directory, canonicalize_error)))?;
+		if !canonical_local_directory.is_dir() {
+			return Err(Error::new_from_message(&format!("Invalid Local Directory - {} is not a directory", local_directory)));
+		}
+		let mut ignore_patterns: Vec<Pattern> = Vec::new();
+		for ignore_pattern in params.ignore_patterns.iter() {
+			let compiled_pattern = Pattern::new(ignore_pattern.trim().trim_start_matches("./"))
+				.map_err(|pattern_error| Error::new_from_message(&format!("Invalid Ignore Pattern {} - Error: {}", ignore_pattern, pattern_error)))?;
+			ignore_patterns.push(compiled_pattern);
+		}
+		return Ok(WatchTarget{
+			local_directory: canonical_local_directory,
+			remote_directory: RemotePath::directory(remote_directory)?,
+			ignore_patterns,
+		});
+	}
+
+	async fn upload_watched_file(&self, local_path: &Path, remote_filepath: &RemotePath, watch_report: &mut WatchReport) {
+		let upload_result = match local_path.to_str() {
+			Some(local_filepath) => self.upload_file(local_filepath, Some(&remote_filepath.to_string())).await,
+			None => Err(Error::new_from_message(&format!("Invalid Local Path - {} is not valid UTF-8", local_path.display()))),
+		};
+		match upload_result {
+			Ok(_) => watch_report.uploaded.push(remote_filepath.to_string()),
+			Err(upload_error) => watch_report.failed.push(WatchFailure{
+				path: remote_filepath.to_string(),
+				error: upload_error,
+			}),
+		}
+	}
+
+	async fn reconcile_watch_target(&self, watch_target: &WatchTarget, local_directory: &Path, delete_remote: bool) -> Result<WatchReport, Error> {
+		let local_files = collect_local_files(local_directory).await?;
+		let remote_subdirectory = match local_directory == watch_target.local_directory {
+			true => watch_target.remote_directory.clone(),
+			false => watch_target.remote_filepath(local_directory)
+				.map(|remote_filepath| remote_filepath.as_directory())
+				.ok_or_else(|| Error::new_from_message(&format!("Invalid Local Directory - {} is ignored or outside of the watched directory", local_directory.display())))?,
+		};
+		let remote_files: HashMap<String, File> = self.get_files_recursive(&remote_subdirectory.to_string()).await?
+			.into_iter()
+			.map(|remote_file| (remote_file.remote_path(), remote_file))
+			.collect();
+		let mut local_remote_paths: BTreeSet<String> = BTreeSet::new();
+		let mut watch_report = WatchReport::default();
+		for local_file in local_files.iter() {
+			let Some(remote_filepath) = watch_target.remote_filepath(local_file) else {
+				continue;
+			};
+			local_remote_paths.insert(remote_filepath.to_string());
+			let is_changed = match remote_files.get(&remote_filepath.to_string()) {
+				Some(remote_file) => is_local_file_changed(local_file, remote_file).await,
+				None => Ok(true),
+			};
+			// The local file may be removed while reconciling, which the watch mirrors separately
+			let is_changed = match is_changed {
+				Ok(is_changed) => is_changed,
+				Err(compare_error) => {
+					watch_report.failed.push(WatchFailure{
+						path: remote_filepath.to_string(),
+						error: compare_error,
+					});
+					continue;
+				},
+			};
+			if is_changed {
+				self.upload_watched_file(local_file, &remote_filepath, &mut watch_report).await;
+			}
+		}
+		if delete_remote {
+			let mut removed_remote_paths: Vec<&String> = remote_files.keys()
+				.filter(|remote_path| !local_remote_paths.contains(*remote_path))
+				.filter(|remote_path| !RemotePath::file(remote_path).ok()
+					.and_then(|remote_filepath| remote_filepath.strip_prefix(&watch_target.remote_directory))
+					.is_some_and(|relative_path| watch_target.is_ignored(&relative_path)))
+				.collect();
+			removed_remote_paths.sort();
+			for removed_remote_path in removed_remote_paths.into_iter() {
+				match self.delete_file(removed_remote_path).await {
+					Ok(_) => watch_report.deleted.push(removed_remote_path.clone()),
+					Err(delete_error) => watch_report.failed.push(WatchFailure{
+						path: removed_remote_path.clone(),
+						error: delete_error,
+					}),
+				}
+			}
+		}
+		return Ok(watch_report);
+	}
+
+	/*
+		Finishes the purge batch of a watch, recording a failed purge in the report instead of ending the watch.
+		Parameters:
+			purge_batch: The batch opened before mirroring the changes
+			watch_target: The watched directories
+			watch_report: The report the purge failure is added to
+	*/
+	async fn finish_watch_purge_batch(&self, purge_batch: PurgeBatch<'_>, watch_target: &WatchTarget, watch_report: &mut WatchReport) {
+		if let Err(purge_error) = purge_batch.finish_checked().await {
+			watch_report.failed.push(WatchFailure{
+				path: watch_target.remote_directory.to_string(),
+				error: purge_error,
+			});
+		}
+	}
+
+	/// Makes the remote directory match the local directory once. Local files which are missing
+	/// or differ remotely are uploaded, and remote files which do not exist locally are deleted
+	/// if delete_remote is set. Files are compared by checksum
+	///
+	/// Parameters:
+	/// * local_directory: The local directory to mirror
+	/// * remote_directory: The directory on bunnystorage the files are mirrored to
+	/// * params: The ignore patterns and whether to delete remote files
+	pub async fn reconcile_directory(&self, local_directory: &str, remote_directory: &str, params: &WatchParameters) -> Result<WatchReport, Error> {
+		self.check_write_password_ok()?;
+		let watch_target = self.prepare_watch_target(local_directory, remote_directory, params)?;
+		let purge_batch = self.begin_purge_batch();
+		let watch_report = self.reconcile_watch_target(&watch_target, &watch_target.local_directory, params.delete_remote).await?;
+		purge_batch.finish_checked().await?;
+		return Ok(watch_report);
+	}
+
+	async fn mirror_changed_path(&self, watch_target: &WatchTarget, changed_path: &Path, delete_remote: bool) -> Result<WatchReport, Error> {
+		let Some(remote_path) = watch_target.remote_filepath(changed_path) else {
+			return Ok(WatchReport::default());
+		};
+		let mut watch_report = WatchReport::default();
+		match fs::metadata(changed_path).await {
+			Ok(local_metadata) if local_metadata.is_dir() => {
+				// A directory which was created or moved into the watched directory
+				return self.reconcile_watch_target(watch_target, changed_path, false).await;
+			},
+			Ok(_) => self.upload_watched_file(changed_path, &remote_path, &mut watch_report).await,
+			Err(metadata_error) if metadata_error.kind() == std::io::ErrorKind::NotFound => {
+				if !delete_remote {
+					return Ok(watch_report);
+				}
+				// The local path no longer exists, so whether it was a file or a directory is only known remotely
+				let delete_result = match self.find_primary_entry(&remote_path).await? {
+					Some(remote_entry) if remote_entry.is_directory() => self.delete_directory(&remote_path.as_directory().to_string()).await,
+					Some(_) => self.delete_file(&remote_path.to_string()).await,
+					None => return Ok(watch_report),
+				};
+				match delete_result {
+					Ok(_) => watch_report.deleted.push(remote_path.to_string()),
+					Err(delete_error) => watch_report.failed.push(WatchFailure{
+						path: remote_path.to_string(),
+						error: delete_error,
+					}),
+				}
+			},
+			Err(metadata_error) => return Err(map_watch_io_error(metadata_error)),
+		}
+		return Ok(watch_report);
+	}
+
+	/// Mirrors changes of a local directory to a remote directory as they happen, until the shutdown
+	/// future completes. Created, modified, renamed and deleted files are detected using filesystem
+	/// notifications. Bursts of changes, e.g. saving a large file or copying a folder, are collected
+	/// until no change has happened for the debounce duration, or for at most max_debounce, and then mirrored together.
+	/// Failing files, purges and notifications do not stop the watch, instead they are recorded in the returned report.
+	///
+	/// # Examples
+	/// ```no_run
+	/// use bunnystorage_rs::client::{BunnyCDNClient, watch::WatchParameters};
+	/// # async fn example() -> Result<(), bunnystorage_rs::errors::Error> {
+	/// let client = BunnyCDNClient::new_from_env()?;
+	/// let watch_params = WatchParameters{
+	///     ignore_patterns: vec!["**/.DS_Store".to_string()],
+	///     ..Default::default()
+	/// };
+	/// let shutdown = async { _ = tokio::signal::ctrl_c().await; };
+	/// let watch_report = client.watch_directory("./assets", "/assets/", &watch_params, shutdown).await?;
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Parameters:
+	/// * local_directory: The local directory to watch, including its subdirectories
+	/// * remote_directory: The directory on bunnystorage the changes are mirrored to
+	/// * params: The debounce durations, the ignore patterns and whether to reconcile and delete remote files
+	/// * shutdown: Stops watching once completed
+	pub async fn watch_directory<F: Future<Output = ()>>(&self, local_directory: &str, remote_directory: &str, params: &WatchParameters, shutdown: F) -> Result<WatchReport, Error> {
+		self.check_write_password_ok()?;
+		let watch_target = self.prepare_watch_target(local_directory, remote_directory, params)?;
+		let (event_sender, mut event_receiver) = mpsc::unbounded::<notify::Result<notify::Event>>();
+		let mut watcher = notify::recommended_watcher(move |watch_event: notify::Result<notify::Event>| {
+			_ = event_sender.unbounded_send(watch_event);
+		}).map_err(|watcher_error| Error::new_from_message(&format!("Failed Creating Watcher - Error: {}", watcher_error)))?;
+		// Watch before reconciling, so changes made while reconciling are not missed
+		watcher.watch(&watch_target.local_directory, RecursiveMode::Recursive)
+			.map_err(|watcher_error| Error::new_from_message(&format!("Failed Watching {} - Error: {}", local_directory, watcher_error)))?;
+		let mut watch_report = WatchReport::default();
+		if params.reconcile_on_start {
+			let purge_batch = self.begin_purge_batch();
+			watch_report = self.reconcile_watch_target(&watch_target, &watch_target.local_directory, params.delete_remote).await?;
+			self.finish_watch_purge_batch(purge_batch, &watch_target, &mut watch_report).await;
+		}
+		let mut shutdown = std::pin::pin!(shutdown.fuse());
+		loop {
+			let first_watch_event = futures::select! {
+				watch_event = event_receiver.next() => watch_event,
+				_ = shutdown => None,
+			};
+			let Some(first_watch_event) = first_watch_event else {
+				break;
+			};
+			let (changed_paths, watcher_errors) = collect_watch_burst(&mut event_receiver, first_watch_event, params).await;
+			// e.g. the event queue of the OS overflowed. The watch keeps running, but changes may have been missed
+			for watcher_error in watcher_errors.into_iter() {
+				watch_report.failed.push(WatchFailure{
+					path: local_directory.to_string(),
+					error: Error::new_from_message(&format!("Failed Watching {} - Error: {}", local_directory, watcher_error)),
+				});
+			}
+			let purge_batch = self.begin_purge_batch();
+			for changed_path in changed_paths.iter() {
+				match self.mirror_changed_path(&watch_target, changed_path, params.delete_remote).await {
+					Ok(changed_report) => watch_report.merge(changed_report),
+					Err(mirror_error) => watch_report.failed.push(WatchFailure{
+						path: changed_path.display().to_string(),
+						error: mirror_error,
+					}),
+				}
+			}
+			self.finish_watch_purge_batch(purge_batch, &watch_target, &mut watch_report).await;
+		}
+		return Ok(watch_report);
+	}
+
+}
+
+#[cfg(test)]
+mod watch_tests {
+	use crate::client::BunnyCDNClientConfig;
+	use super::*;
+
+	#[test]
+	fn test_watch_target_remote_filepath() {
+		let client = BunnyCDNClient::new(BunnyCDNClientConfig::new_offline()).unwrap();
+		let watch_params = WatchParameters{
+			ignore_patterns: vec!["**/.DS_Store".to_string(), "*.tmp".to_string()],
+			..Default::default()
+		};
+		let test_local_directory = std::env::temp_dir().join("bunnystorage_watch_test");
+		assert!(std::fs::create_dir_all(&test_local_directory).is_ok());
+		let watch_target = client.prepare_watch_target(test_local_directory.to_str().unwrap(), "/assets", &watch_params).unwrap();
+		let local_directory = watch_target.local_directory.clone();
+		let remote_filepath = watch_target.remote_filepath(&local_directory.join("source").join("Test_Image.jpg"));
+		assert!(remote_filepath.is_some_and(|remote_filepath| remote_filepath.to_string() == "assets/source/Test_Image.jpg"));
+		assert!(watch_target.remote_filepath(&local_directory.join("source").join(".DS_Store")).is_none());
+		assert!(watch_target.remote_filepath(&local_directory.join("upload.tmp")).is_none());
+		assert!(watch_target.remote_filepath(&local_directory).is_none());
+		assert!(watch_target.remote_filepath(Path::new("/etc/passwd")).is_none());
+		assert!(client.prepare_watch_target(test_local_directory.join("missing").to_str().unwrap(), "/assets", &watch_params).is_err());
+	}
+
+	#[tokio::test]
+	async fn test_collect_watch_burst() {
+		let watch_params = WatchParameters{
+			debounce: Duration::from_millis(50),
+			max_debounce: Duration::from_millis(200),
+			..Default::default()
+		};
+		let (event_sender, mut event_receiver) = mpsc::unbounded::<notify::Result<notify::Event>>();
+		let new_watch_event = |path: &str| Ok(notify::Event::new(EventKind::Any).add_path(PathBuf::from(path)));
+		// A file which is written continuously, faster than the debounce duration
+		let sender_handle = tokio::spawn(async move {
+			while event_sender.unbounded_send(new_watch_event("app.log")).is_ok() {
+				tokio::time::sleep(Duration::from_millis(10)).await;
+			}
+		});
+		let collect_result = tokio::time::timeout(Duration::from_secs(5), collect_watch_burst(&mut event_receiver, new_watch_event("index.html"), &watch_params)).await;
+		assert!(collect_result.is_ok_and(|(changed_paths, watcher_errors)| changed_paths.len() == 2 && watcher_errors.is_empty()));
+		sender_handle.abort();
+	}
+
+	#[tokio::test]
+	async fn test_reconcile_directory() {
+		let client_result: Result<BunnyCDNClient, Error> = BunnyCDNClient::new_from_env();
+		assert!(client_result.is_ok());
+		let client = client_result.unwrap();
+		let reconcile_result = client.reconcile_directory("./tests/files/source", "/tests/files/watch/", &WatchParameters::default()).await;
+		assert!(reconcile_result.is_ok_and(|watch_report| watch_report.failed.is_empty()));
+		// Nothing has changed locally, so nothing is uploaded again
+		let repeated_reconcile_result = client.reconcile_directory("./tests/files/source", "/tests/files/watch/", &WatchParameters::default()).await;
+		assert!(repeated_reconcile_result.is_ok_and(|watch_report| watch_report.uploaded.is_empty()));
+		assert!(client.delete_directory("/tests/files/watch/").await.is_ok());
+	}
+}
@@ -41,4 +41,6 @@ impl Display for Error {
 		}
 		return write!(f, "{}", displayed_error_message);
 	}
-}
\ No newline at end of file
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,392 @@
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::Url;
+
+use crate::{client::publicurl::select_public_url_base, errors::Error, models::pullzone::PullZone, remotepath::RemotePath};
+
+const MAX_OPTIMIZER_QUALITY: u8 = 100;
+const MAX_OPTIMIZER_BLUR: u8 = 100;
+const MAX_OPTIMIZER_BRIGHTNESS: i8 = 100;
+
+// Characters which are kept as is in the values of the optimizer parameters
+const OPTIMIZER_PARAMETER_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+	.remove(b'-')
+	.remove(b'_')
+	.remove(b'.')
+	.remove(b':')
+	.remove(b',');
+
+const WIDTH_PARAMETER_NAME: &str = "width";
+const HEIGHT_PARAMETER_NAME: &str = "height";
+const ASPECT_RATIO_PARAMETER_NAME: &str = "aspect_ratio";
+const QUALITY_PARAMETER_NAME: &str = "quality";
+const CROP_PARAMETER_NAME: &str = "crop";
+const CROP_GRAVITY_PARAMETER_NAME: &str = "crop_gravity";
+const FLIP_PARAMETER_NAME: &str = "flip";
+const SHARPEN_PARAMETER_NAME: &str = "sharpen";
+const BLUR_PARAMETER_NAME: &str = "blur";
+const BRIGHTNESS_PARAMETER_NAME: &str = "brightness";
+const CLASS_PARAMETER_NAME: &str = "class";
+
+/// Which part of the image is kept when cropping
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CropGravity {
+	Center,
+	Forget,
+	North,
+	NorthEast,
+	East,
+	SouthEast,
+	South,
+	SouthWest,
+	West,
+	NorthWest,
+}
+
+impl CropGravity {
+
+	pub fn as_str(&self) -> &'static str {
+		return match self {
+			CropGravity::Center => "center",
+			CropGravity::Forget => "forget",
+			CropGravity::North => "north",
+			CropGravity::NorthEast => "northeast",
+			CropGravity::East => "east",
+			CropGravity::SouthEast => "southeast",
+			CropGravity::South => "south",
+			CropGravity::SouthWest => "southwest",
+			CropGravity::West => "west",
+			CropGravity::NorthWest => "northwest",
+		};
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct OptimizerCrop {
+	width: u32,
+	height: u32,
+	// The top left corner of the crop. If None, then the crop gravity positions the crop
+	offset: Option<(u32, u32)>,
+}
+
+/// Builds URLs which transform images with the Bunny Optimizer of a pull zone.
+/// The values are validated when the URL is built
+///
+/// # Examples
+/// ```
+/// use bunnystorage_rs::imageoptimizer::{CropGravity, OptimizerUrlBuilder};
+/// let optimized_url = OptimizerUrlBuilder::new()
+///     .width(400)
+///     .aspect_ratio(16, 9)
+///     .crop_gravity(CropGravity::Center)
+///     .quality(80)
+///     .apply_to_url("https://cdn.example.com/images/hero.jpg");
+/// assert_eq!(optimized_url.unwrap(), "https://cdn.example.com/images/hero.jpg?width=400&aspect_ratio=16:9&quality=80&crop_gravity=center");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OptimizerUrlBuilder {
+	width: Option<u32>,
+	height: Option<u32>,
+	aspect_ratio: Option<(u32, u32)>,
+	quality: Option<u8>,
+	crop: Option<OptimizerCrop>,
+	crop_gravity: Option<CropGravity>,
+	flip: bool,
+	sharpen: bool,
+	blur: Option<u8>,
+	brightness: Option<i8>,
+	class: Option<String>,
+}
+
+impl OptimizerUrlBuilder {
+
+	pub fn new() -> OptimizerUrlBuilder {
+		return OptimizerUrlBuilder::default();
+	}
+
+	/// The width in pixels. The height follows the aspect ratio unless provided as well
+	pub fn width(mut self, width: u32) -> Self {
+		self.width = Some(width);
+		return self;
+	}
+
+	/// The height in pixels. The width follows the aspect ratio unless provided as well
+	pub fn height(mut self, height: u32) -> Self {
+		self.height = Some(height);
+		return self;
+	}
+
+	/// Crops the image to the aspect ratio, e.g. 16:9
+	pub fn aspect_ratio(mut self, width_ratio: u32, height_ratio: u32) -> Self {
+		self.aspect_ratio = Some((width_ratio, height_ratio));
+		return self;
+	}
+
+	/// The compression quality between 0 and 100
+	pub fn quality(mut self, quality: u8) -> Self {
+		self.quality = Some(quality);
+		return self;
+	}
+
+	/// Crops the image to the size, positioned by the crop gravity
+	pub fn crop(mut self, width: u32, height: u32) -> Self {
+		self.crop = Some(OptimizerCrop{ width, height, offset: None });
+		return self;
+	}
+
+	/// Crops the image to the size, starting at the top left corner x, y
+	pub fn crop_at(mut self, width: u32, height: u32, x: u32, y: u32) -> Self {
+		self.crop = Some(OptimizerCrop{ width, height, offset: Some((x, y)) });
+		return self;
+	}
+
+	pub fn crop_gravity(mut self, crop_gravity: CropGravity) -> Self {
+		self.crop_gravity = Some(crop_gravity);
+		return self;
+	}
+
+	/// Flips the image vertically
+	pub fn flip(mut self, flip: bool) -> Self {
+		self.flip = flip;
+		return self;
+	}
+
+	pub fn sharpen(mut self, sharpen: bool) -> Self {
+		self.sharpen = sharpen;
+		return self;
+	}
+
+	/// The blur strength between 0 and 100
+	pub fn blur(mut self, blur: u8) -> Self {
+		self.blur = Some(blur);
+		return self;
+	}
+
+	/// The brightness adjustment between -100 and 100
+	pub fn brightness(mut self, brightness: i8) -> Self {
+		self.brightness = Some(brightness);
+		return self;
+	}
+
+	/// Applies an optimizer class of the pull zone, which is a named set of the parameters
+	pub fn class(mut self, class: &str) -> Self {
+		self.class = Some(class.to_string());
+		return self;
+	}
+
+	fn has_manipulations(&self) -> bool {
+		return self.width.is_some()
+			|| self.height.is_some()
+			|| self.aspect_ratio.is_some()
+			|| self.quality.is_some()
+			|| self.crop.is_some()
+			|| self.crop_gravity.is_some()
+			|| self.flip
+			|| self.sharpen
+			|| self.blur.is_some()
+			|| self.brightness.is_some();
+	}
+
+	/// Checks the values are in the ranges accepted by the optimizer
+	pub fn validate(&self) -> Result<(), Error> {
+		if self.width == Some(0) || self.height == Some(0) {
+			return Err(Error::new_from_message("Invalid Optimizer Size - Width and height must be at least 1"));
+		}
+		if let Some((width_ratio, height_ratio)) = self.aspect_ratio {
+			if width_ratio == 0 || height_ratio == 0 {
+				return Err(Error::new_from_message(&format!("Invalid Optimizer Aspect Ratio - Provided: {}:{}", width_ratio, height_ratio)));
+			}
+		}
+		if let Some(quality) = self.quality.filter(|quality| *quality > MAX_OPTIMIZER_QUALITY) {
+			return Err(Error::new_from_message(&format!("Invalid Optimizer Quality - Must be between 0 and {}, Provided: {}", MAX_OPTIMIZER_QUALITY, quality)));
+		}
+		if let Some(crop) = self.crop {
+			if crop.width == 0 || crop.height == 0 {
+				return Err(Error::new_from_message(&format!("Invalid Optimizer Crop - Provided: {}x{}", crop.width, crop.height)));
+			}
+			if crop.offset.is_some() && self.crop_gravity.is_some() {
+				return Err(Error::new_from_message("Invalid Optimizer Crop - A crop with a position cannot use a crop gravity as well"));
+			}
+		}
+		if let Some(blur) = self.blur.filter(|blur| *blur > MAX_OPTIMIZER_BLUR) {
+			return Err(Error::new_from_message(&format!("Invalid Optimizer Blur - Must be between 0 and {}, Provided: {}", MAX_OPTIMIZER_BLUR, blur)));
+		}
+		if let Some(brightness) = self.brightness.filter(|brightness| !(-MAX_OPTIMIZER_BRIGHTNESS..=MAX_OPTIMIZER_BRIGHTNESS).contains(brightness)) {
+			return Err(Error::new_from_message(&format!("Invalid Optimizer Brightness - Must be between -{} and {}, Provided: {}", MAX_OPTIMIZER_BRIGHTNESS, MAX_OPTIMIZER_BRIGHTNESS, brightness)));
+		}
+		if self.class.as_ref().is_some_and(|class| class.trim().is_empty()) {
+			return Err(Error::new_from_message("Invalid Optimizer Class - Must not be empty"));
+		}
+		return Ok(());
+	}
+
+	/*
+		Checks the pull zone settings allow the parameters.
+		Parameters:
+			optimizer_enabled: Whether the optimizer is enabled for the pull zone
+			manipulation_engine_enabled: Whether the pull zone allows transforming images through query parameters
+			force_classes: Whether the pull zone only allows optimizer classes
+			class_names: The names of the optimizer classes of the pull zone
+	*/
+	fn validate_optimizer_settings(&self, optimizer_enabled: bool, manipulation_engine_enabled: bool, force_classes: bool, class_names: &[&str]) -> Result<(), Error> {
+		if !optimizer_enabled {
+			return Err(Error::new_from_message("Optimizer Is Disabled For The Pull Zone"));
+		}
+		if self.has_manipulations() {
+			if !manipulation_engine_enabled {
+				return Err(Error::new_from_message("Image Manipulation Is Disabled For The Pull Zone"));
+			}
+			if force_classes {
+				return Err(Error::new_from_message("The Pull Zone Only Allows Optimizer Classes"));
+			}
+		}
+		if let Some(class) = self.class.as_deref() {
+			if !class_names.contains(&class) {
+				return Err(Error::new_from_message(&format!("Optimizer Class Not Found - {}", class)));
+			}
+		}
+		return Ok(());
+	}
+
+	/// The optimizer query parameters in the order they are appended
+	pub fn query_pairs(&self) -> Vec<(&'static str, String)> {
+		let mut query_pairs: Vec<(&'static str, String)> = Vec::new();
+		if let Some(class) = &self.class {
+			query_pairs.push((CLASS_PARAMETER_NAME, class.clone()));
+		}
+		if let Some(width) = self.width {
+			query_pairs.push((WIDTH_PARAMETER_NAME, width.to_string()));
+		}
+		if let Some(height) = self.height {
+			query_pairs.push((HEIGHT_PARAMETER_NAME, height.to_string()));
+		}
+		if let Some((width_ratio, height_ratio)) = self.aspect_ratio {
+			query_pairs.push((ASPECT_RATIO_PARAMETER_NAME, format!("{}:{}", width_ratio, height_ratio)));
+		}
+		if let Some(quality) = self.quality {
+			query_pairs.push((QUALITY_PARAMETER_NAME, quality.to_string()));
+		}
+		if let Some(crop) = self.crop {
+			let crop_value = match crop.offset {
+				Some((x, y)) => format!("{},{},{},{}", crop.width, crop.height, x, y),
+				None => format!("{},{}", crop.width, crop.height),
+			};
+			query_pairs.push((CROP_PARAMETER_NAME, crop_value));
+		}
+		if let Some(crop_gravity) = self.crop_gravity {
+			query_pairs.push((CROP_GRAVITY_PARAMETER_NAME, crop_gravity.as_str().to_string()));
+		}
+		if self.flip {
+			query_pairs.push((FLIP_PARAMETER_NAME, "true".to_string()));
+		}
+		if self.sharpen {
+			query_pairs.push((SHARPEN_PARAMETER_NAME, "true".to_string()));
+		}
+		if let Some(blur) = self.blur {
+			query_pairs.push((BLUR_PARAMETER_NAME, blur.to_string()));
+		}
+		if let Some(brightness) = self.brightness {
+			query_pairs.push((BRIGHTNESS_PARAMETER_NAME, brightness.to_string()));
+		}
+		return query_pairs;
+	}
+
+	/// Appends the optimizer parameters to the URL, replacing optimizer parameters already in it.
+	/// Other query parameters are kept
+	///
+	/// Parameters:
+	/// * url: The absolute URL of an image on a pull zone
+	pub fn apply_to_url(&self, url: &str) -> Result<String, Error> {
+		self.validate()?;
+		let mut optimized_url = Url::parse(url)
+			.map_err(|parse_error| Error::new_from_message(&format!("Invalid URL {} - Error: {}", url, parse_error)))?;
+		let optimizer_parameter_names = [
+			WIDTH_PARAMETER_NAME, HEIGHT_PARAMETER_NAME, ASPECT_RATIO_PARAMETER_NAME, QUALITY_PARAMETER_NAME,
+			CROP_PARAMETER_NAME, CROP_GRAVITY_PARAMETER_NAME, FLIP_PARAMETER_NAME, SHARPEN_PARAMETER_NAME,
+			BLUR_PARAMETER_NAME, BRIGHTNESS_PARAMETER_NAME, CLASS_PARAMETER_NAME,
+		];
+		let kept_query_pairs: Vec<(String, String)> = optimized_url.query_pairs()
+			.filter(|(parameter_name, _)| !optimizer_parameter_names.contains(&parameter_name.as_ref()))
+			.map(|(parameter_name, parameter_value)| (parameter_name.into_owned(), parameter_value.into_owned()))
+			.collect();
+		optimized_url.set_query(None);
+		if !kept_query_pairs.is_empty() {
+			optimized_url.query_pairs_mut().extend_pairs(kept_query_pairs.iter());
+		}
+		let mut optimized_query: Vec<String> = optimized_url.query().map(|kept_query| vec![kept_query.to_string()]).unwrap_or_default();
+		// Only the class is free text, the separators of aspect_ratio and crop are kept readable
+		for (parameter_name, parameter_value) in self.query_pairs().iter() {
+			optimized_query.push(format!("{}={}", parameter_name, utf8_percent_encode(parameter_value, OPTIMIZER_PARAMETER_ENCODE_SET)));
+		}
+		if optimized_query.is_empty() {
+			return Ok(optimized_url.to_string());
+		}
+		optimized_url.set_query(Some(&optimized_query.join("&")));
+		return Ok(optimized_url.to_string());
+	}
+
+	/// The optimized URL of a file on the pull zone, using the preferred hostname of the pull zone.
+	/// Fails if the optimizer settings of the pull zone do not allow the parameters
+	///
+	/// Parameters:
+	/// * pull_zone: The pull zone serving the file
+	/// * remote_filepath: The filepath relative to the root of the pull zone
+	pub fn build_url(&self, pull_zone: &PullZone, remote_filepath: &str) -> Result<String, Error> {
+		self.validate()?;
+		let class_names: Vec<&str> = pull_zone.optimizer_classes.iter()
+			.map(|optimizer_class| optimizer_class.name.as_str())
+			.collect();
+		self.validate_optimizer_settings(
+			pull_zone.optimizer_enabled,
+			pull_zone.optimizer_enable_manipulation_engine,
+			pull_zone.optimizer_force_classes,
+			&class_names,
+		)?;
+		let used_remote_filepath = RemotePath::file(remote_filepath)?;
+		let public_url_base = select_public_url_base(&pull_zone.hostnames)
+			.ok_or_else(|| Error::new_from_message(&format!("No Hostname For Pull Zone {}", pull_zone.name)))?;
+		return self.apply_to_url(&format!("{}/{}", public_url_base, used_remote_filepath.to_url_path()));
+	}
+
+}
+
+#[cfg(test)]
+mod image_optimizer_tests {
+	use super::*;
+
+	#[test]
+	fn test_validate_optimizer_parameters() {
+		assert!(OptimizerUrlBuilder::new().width(400).quality(100).brightness(-100).blur(100).validate().is_ok());
+		assert!(OptimizerUrlBuilder::new().width(0).validate().is_err());
+		assert!(OptimizerUrlBuilder::new().aspect_ratio(16, 0).validate().is_err());
+		assert!(OptimizerUrlBuilder::new().quality(101).validate().is_err());
+		assert!(OptimizerUrlBuilder::new().blur(101).validate().is_err());
+		assert!(OptimizerUrlBuilder::new().brightness(-101).validate().is_err());
+		assert!(OptimizerUrlBuilder::new().brightness(i8::MIN).validate().is_err());
+		assert!(OptimizerUrlBuilder::new().crop(0, 100).validate().is_err());
+		assert!(OptimizerUrlBuilder::new().crop_at(100, 100, 10, 10).crop_gravity(CropGravity::North).validate().is_err());
+		assert!(OptimizerUrlBuilder::new().class(" ").validate().is_err());
+		let resize_builder = OptimizerUrlBuilder::new().width(400);
+		assert!(resize_builder.validate_optimizer_settings(false, true, false, &[]).is_err());
+		assert!(resize_builder.validate_optimizer_settings(true, false, false, &[]).is_err());
+		assert!(resize_builder.validate_optimizer_settings(true, true, true, &[]).is_err());
+		assert!(resize_builder.validate_optimizer_settings(true, true, false, &[]).is_ok());
+		let class_builder = OptimizerUrlBuilder::new().class("thumbnail");
+		assert!(class_builder.validate_optimizer_settings(true, false, true, &["thumbnail"]).is_ok());
+		assert!(class_builder.validate_optimizer_settings(true, false, true, &["banner"]).is_err());
+	}
+
+	#[test]
+	fn test_apply_to_url() {
+		let optimizer_builder = OptimizerUrlBuilder::new()
+			.height(300)
+			.crop_at(200, 100, 5, 10)
+			.flip(true)
+			.sharpen(true)
+			.brightness(-20);
+		let optimized_url = optimizer_builder.apply_to_url("https://cdn.example.com/images/my%20logo.png?v=2&width=100");
+		assert_eq!(optimized_url.unwrap(), "https://cdn.example.com/images/my%20logo.png?v=2&height=300&crop=200,100,5,10&flip=true&sharpen=true&brightness=-20");
+		assert_eq!(OptimizerUrlBuilder::new().apply_to_url("https://cdn.example.com/logo.png?width=100").unwrap(), "https://cdn.example.com/logo.png");
+		assert_eq!(OptimizerUrlBuilder::new().class("thumbnail").apply_to_url("https://cdn.example.com/logo.png").unwrap(), "https://cdn.example.com/logo.png?class=thumbnail");
+		assert!(OptimizerUrlBuilder::new().quality(101).apply_to_url("https://cdn.example.com/logo.png").is_err());
+	}
+}
@@ -2,6 +2,10 @@ pub mod errors;
 pub mod client;
 pub mod models;
 pub mod environment;
+pub mod remotepath;
+pub mod store;
+pub mod urlsigning;
+pub mod imageoptimizer;
 
 #[cfg(test)]
 mod tests {
@@ -15,6 +15,8 @@ pub mod file;
 pub mod optimizerclass;
 pub mod bunnyaiimageblueprint;
 pub mod storagezonestatistics;
+pub mod usagereport;
+pub mod inventory;
 
 const YYYYMMDDHHMMSS: &str = "%Y-%m-%d%H:%M:%S";
 const YYYYMMDDHHMMSS_MILLI: &str = "%Y-%m-%d%H:%M:%S.%f";
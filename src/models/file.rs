@@ -1,9 +1,59 @@
+use std::fmt::Display;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use crate::models::{serialize_datetime, deserialize_datetime};
+use crate::{errors::Error, models::{serialize_datetime, deserialize_datetime}, remotepath::RemotePath};
+
+/// The checksum Bunnystorage calculates for a stored file.
+/// Bunnystorage reports it as a hexadecimal SHA256 digest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileChecksum {
+	Sha256([u8; 32]),
+}
+
+impl FileChecksum {
+
+	/// Parses a hexadecimal SHA256 digest in either case, ignoring surrounding whitespace
+	pub fn parse(checksum: &str) -> Result<FileChecksum, Error> {
+		let trimmed_checksum = checksum.trim();
+		if trimmed_checksum.len() != 64 || !trimmed_checksum.is_ascii() {
+			return Err(Error::new_from_message(&format!("Invalid Checksum - Expected 64 hexadecimal characters. Provided: {}", checksum)));
+		}
+		let mut digest = [0u8; 32];
+		for (digest_index, digest_byte) in digest.iter_mut().enumerate() {
+			let hex_pair = &trimmed_checksum[digest_index * 2..digest_index * 2 + 2];
+			*digest_byte = u8::from_str_radix(hex_pair, 16)
+				.map_err(|parse_error| Error::new_from_message(&format!("Invalid Checksum - {}", parse_error)))?;
+		}
+		return Ok(FileChecksum::Sha256(digest));
+	}
+
+	/// The raw digest
+	pub fn bytes(&self) -> &[u8] {
+		match self {
+			FileChecksum::Sha256(digest) => digest,
+		}
+	}
+
+	/// The checksum as uppercase hexadecimal, which is the format used by Bunnystorage
+	pub fn to_hex(&self) -> String {
+		return self.bytes().iter()
+			.map(|digest_byte| format!("{:02X}", digest_byte))
+			.collect::<Vec<String>>()
+			.join("");
+	}
+}
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all(serialize = "camelCase", deserialize = "PascalCase"))]
+impl Display for FileChecksum {
+
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		return write!(f, "{}", self.to_hex());
+	}
+}
+
+// See https://docs.bunny.net/reference/get_-storagezonename-path- for further documentation
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
 pub struct File {
 	guid: String,
 	storage_zone_name: String,
@@ -22,4 +72,268 @@ pub struct File {
 	storage_zone_id: u32,
 	checksum: Option<String>,
 	replicated_zones: Option<String>,
-}
\ No newline at end of file
+}
+
+impl File {
+
+	/*
+		Builds the entry of a file from the headers of a HEAD request, for when listing the parent is not needed.
+		Fields which are only available in the listing are left empty, e.g. the GUID and the checksum.
+		Parameters:
+			storage_zone_name: The name of the storage zone containing the file
+			remote_filepath: The filepath relative to the root of the storage zone
+			length: The size of the file in bytes
+			content_type: The content type from the Content-Type header
+			last_changed: The time from the Last-Modified header. If None, then the Unix epoch is used
+	*/
+	pub(crate) fn new_from_head(storage_zone_name: &str, remote_filepath: &RemotePath, length: u64, content_type: Option<&str>, last_changed: Option<DateTime<Utc>>) -> File {
+		let parent_directory = remote_filepath.parent().unwrap_or_else(RemotePath::root);
+		let used_last_changed = last_changed.unwrap_or(DateTime::UNIX_EPOCH);
+		return File{
+			guid: String::new(),
+			storage_zone_name: storage_zone_name.to_string(),
+			path: format!("/{}/{}", storage_zone_name, parent_directory),
+			object_name: remote_filepath.file_name().unwrap_or_default().to_string(),
+			length,
+			last_changed: used_last_changed,
+			server_id: 0,
+			array_number: 0,
+			is_directory: false,
+			user_id: String::new(),
+			content_type: content_type.unwrap_or_default().to_string(),
+			date_created: used_last_changed,
+			storage_zone_id: 0,
+			checksum: None,
+			replicated_zones: None,
+		};
+	}
+
+	/// The unique ID of the entry
+	pub fn guid(&self) -> &str {
+		return &self.guid;
+	}
+
+	/// The name of the storage zone containing the entry
+	pub fn storage_zone_name(&self) -> &str {
+		return &self.storage_zone_name;
+	}
+
+	/// The directory of the entry as reported by Bunnystorage, which includes the storage zone name
+	pub fn path(&self) -> &str {
+		return &self.path;
+	}
+
+	/// The name of the file or directory
+	pub fn object_name(&self) -> &str {
+		return &self.object_name;
+	}
+
+	/// The size of the file in bytes
+	pub fn length(&self) -> u64 {
+		return self.length;
+	}
+
+	/// When the entry was last changed
+	pub fn last_changed(&self) -> DateTime<Utc> {
+		return self.last_changed;
+	}
+
+	/// The ID of the storage server holding the entry
+	pub fn server_id(&self) -> u32 {
+		return self.server_id;
+	}
+
+	/// The storage array on the server holding the entry
+	pub fn array_number(&self) -> u32 {
+		return self.array_number;
+	}
+
+	/// Whether the entry is a directory instead of a file
+	pub fn is_directory(&self) -> bool {
+		return self.is_directory;
+	}
+
+	/// The ID of the user owning the storage zone
+	pub fn user_id(&self) -> &str {
+		return &self.user_id;
+	}
+
+	/// The content type of the file. Empty for directories and when not reported
+	pub fn content_type(&self) -> &str {
+		return &self.content_type;
+	}
+
+	/// When the entry was created
+	pub fn date_created(&self) -> DateTime<Utc> {
+		return self.date_created;
+	}
+
+	/// The ID of the storage zone containing the entry
+	pub fn storage_zone_id(&self) -> u32 {
+		return self.storage_zone_id;
+	}
+
+	/// The checksum exactly as reported by Bunnystorage
+	pub fn checksum(&self) -> Option<&str> {
+		return self.checksum.as_deref();
+	}
+
+	/// The checksum parsed into a FileChecksum. Directories and files where Bunnystorage
+	/// has not calculated a checksum yet return None, as do malformed checksums
+	pub fn parsed_checksum(&self) -> Option<FileChecksum> {
+		return self.checksum.as_deref()
+			.filter(|checksum| !checksum.trim().is_empty())
+			.and_then(|checksum| FileChecksum::parse(checksum).ok());
+	}
+
+	/// The replicated zones exactly as reported by Bunnystorage
+	pub fn replicated_zones(&self) -> Option<&str> {
+		return self.replicated_zones.as_deref();
+	}
+
+	/// The region codes, e.g. DE, NY, the file has been replicated to.
+	/// Bunnystorage reports these as a single comma separated string
+	pub fn replicated_region_codes(&self) -> Vec<String> {
+		return match &self.replicated_zones {
+			None => Vec::new(),
+			Some(replicated_zones) => replicated_zones
+				.split(',')
+				.map(|region_code| region_code.trim().to_uppercase())
+				.filter(|region_code| !region_code.is_empty())
+				.collect(),
+		};
+	}
+
+	/// The path of the entry relative to the root of the storage zone.
+	/// Bunnystorage reports the path prefixed with the storage zone name
+	/// e.g. /myzone/some/directory/ which is stripped here.
+	/// Directories are returned with a trailing /
+	pub fn remote_path(&self) -> String {
+		let zone_prefix = format!("/{}/", self.storage_zone_name);
+		let mut directory = self.path.as_str();
+		if let Some(stripped_directory) = directory.strip_prefix(&zone_prefix) {
+			directory = stripped_directory;
+		} else {
+			directory = directory.trim_start_matches('/');
+		}
+		let mut remote_path = format!("{}{}", directory, self.object_name);
+		if self.is_directory {
+			remote_path.push('/');
+		}
+		return remote_path;
+	}
+
+	/// The remote path as a RemotePath, keeping the distinction between files and directories
+	pub fn to_remote_path(&self) -> Result<RemotePath, Error> {
+		return RemotePath::parse(&self.remote_path());
+	}
+}
+
+/// Builds listing entries for tests without a Bunnystorage response. The entry is a file
+/// in the storage zone myzone, created 2024-01-01 and last changed 2024-03-01
+#[cfg(test)]
+pub(crate) struct TestFileBuilder {
+	file: File,
+}
+
+#[cfg(test)]
+impl TestFileBuilder {
+
+	fn parse_test_datetime(datetime: &str) -> DateTime<Utc> {
+		return chrono::NaiveDateTime::parse_from_str(datetime, "%Y-%m-%dT%H:%M:%S%.f").unwrap().and_utc();
+	}
+
+	pub(crate) fn new(remote_filepath: &str) -> TestFileBuilder {
+		let mut file = File::new_from_head("myzone", &RemotePath::file(remote_filepath).unwrap(), 0, None, None);
+		file.guid = "6b0b7c2a-5d1f-4a43-9f0e-4d7d0f3f1a11".to_string();
+		file.server_id = 12;
+		file.user_id = "user".to_string();
+		file.storage_zone_id = 1234;
+		file.last_changed = TestFileBuilder::parse_test_datetime("2024-03-01T00:00:00");
+		file.date_created = TestFileBuilder::parse_test_datetime("2024-01-01T00:00:00");
+		return TestFileBuilder{ file };
+	}
+
+	pub(crate) fn length(mut self, length: u64) -> TestFileBuilder {
+		self.file.length = length;
+		return self;
+	}
+
+	pub(crate) fn last_changed(mut self, last_changed: &str) -> TestFileBuilder {
+		self.file.last_changed = TestFileBuilder::parse_test_datetime(last_changed);
+		return self;
+	}
+
+	pub(crate) fn date_created(mut self, date_created: &str) -> TestFileBuilder {
+		self.file.date_created = TestFileBuilder::parse_test_datetime(date_created);
+		return self;
+	}
+
+	pub(crate) fn checksum(mut self, checksum: &str) -> TestFileBuilder {
+		self.file.checksum = Some(checksum.to_string());
+		return self;
+	}
+
+	pub(crate) fn build(self) -> File {
+		return self.file;
+	}
+}
+
+#[cfg(test)]
+mod file_tests {
+	use super::*;
+
+	const TEST_FILE_JSON: &str = r#"{
+		"Guid": "6b0b7c2a-5d1f-4a43-9f0e-4d7d0f3f1a11",
+		"StorageZoneName": "myzone",
+		"Path": "/myzone/images/icons/",
+		"ObjectName": "logo.png",
+		"Length": 1024,
+		"LastChanged": "2025-03-01T10:15:30.123",
+		"ServerId": 12,
+		"ArrayNumber": 0,
+		"IsDirectory": false,
+		"UserId": "user",
+		"ContentType": "",
+		"DateCreated": "2025-03-01T10:15:30.123",
+		"StorageZoneId": 1234,
+		"Checksum": "9F86D081884C7D659A2FEAA0C55AD015A3BF4F1B2B0B822CD15D6C15B0F00A08",
+		"ReplicatedZones": "UK, ny,"
+	}"#;
+
+	#[test]
+	fn test_file_accessors() {
+		let file_result: Result<File, serde_json::Error> = serde_json::from_str(TEST_FILE_JSON);
+		assert!(file_result.is_ok());
+		let file = file_result.unwrap();
+		assert_eq!(file.remote_path(), "images/icons/logo.png");
+		assert!(file.to_remote_path().is_ok_and(|remote_path| remote_path.is_file()));
+		assert_eq!(file.replicated_region_codes(), vec!["UK".to_string(), "NY".to_string()]);
+		let parsed_checksum = file.parsed_checksum();
+		assert!(parsed_checksum.is_some());
+		assert_eq!(parsed_checksum.unwrap().to_hex(), file.checksum().unwrap());
+		assert!(FileChecksum::parse("not a checksum").is_err());
+	}
+
+	#[test]
+	fn test_file_round_trip() {
+		let file: File = serde_json::from_str(TEST_FILE_JSON).unwrap();
+		let serialized_file_result = serde_json::to_string(&file);
+		assert!(serialized_file_result.is_ok());
+		let round_trip_file_result: Result<File, serde_json::Error> = serde_json::from_str(&serialized_file_result.unwrap());
+		assert!(round_trip_file_result.is_ok());
+		assert_eq!(round_trip_file_result.unwrap(), file);
+	}
+
+	#[test]
+	fn test_file_from_head() {
+		let remote_filepath = RemotePath::file("images/icons/logo.png").unwrap();
+		let head_file = File::new_from_head("myzone", &remote_filepath, 1024, Some("image/png"), None);
+		assert_eq!(head_file.remote_path(), "images/icons/logo.png");
+		assert_eq!(head_file.length(), 1024);
+		assert_eq!(head_file.content_type(), "image/png");
+		assert!(head_file.parsed_checksum().is_none());
+		let root_file = File::new_from_head("myzone", &RemotePath::file("logo.png").unwrap(), 0, None, None);
+		assert_eq!(root_file.remote_path(), "logo.png");
+	}
+}
@@ -0,0 +1,67 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{deserialize_datetime, file::File, serialize_datetime};
+
+pub const INVENTORY_CSV_HEADER: [&str; 8] = [
+	"path",
+	"length",
+	"checksum",
+	"content_type",
+	"date_created",
+	"last_changed",
+	"replicated_zones",
+	"guid",
+];
+
+/// A single file of a storage zone inventory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryRecord {
+	// The path relative to the root of the storage zone
+	pub path: String,
+	pub length: u64,
+	// The SHA256 checksum as uppercase hexadecimal. None if Bunnystorage has not calculated it yet
+	pub checksum: Option<String>,
+	pub content_type: String,
+	#[serde(serialize_with = "serialize_datetime", deserialize_with = "deserialize_datetime")]
+	pub date_created: DateTime<Utc>,
+	#[serde(serialize_with = "serialize_datetime", deserialize_with = "deserialize_datetime")]
+	pub last_changed: DateTime<Utc>,
+	// The region codes the file has been replicated to e.g. DE, NY
+	pub replicated_zones: Vec<String>,
+	pub guid: String,
+}
+
+impl InventoryRecord {
+
+	/// The columns in the order of INVENTORY_CSV_HEADER. The replicated zones are joined by ;
+	pub fn csv_row(&self) -> [String; 8] {
+		return [
+			self.path.clone(),
+			self.length.to_string(),
+			self.checksum.clone().unwrap_or_default(),
+			self.content_type.clone(),
+			self.date_created.to_rfc3339(),
+			self.last_changed.to_rfc3339(),
+			self.replicated_zones.join(";"),
+			self.guid.clone(),
+		];
+	}
+}
+
+impl From<&File> for InventoryRecord {
+
+	fn from(file: &File) -> Self {
+		return InventoryRecord{
+			path: file.remote_path(),
+			length: file.length(),
+			checksum: file.parsed_checksum().map(|checksum| checksum.to_hex()),
+			content_type: file.content_type().to_string(),
+			date_created: file.date_created(),
+			last_changed: file.last_changed(),
+			replicated_zones: file.replicated_region_codes(),
+			guid: file.guid().to_string(),
+		};
+	}
+}
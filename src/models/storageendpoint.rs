@@ -2,6 +2,7 @@ use std::fmt::Display;
 
 use crate::errors::Error;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum StorageEndpoint {
 	Falkenstein,
 	London,
@@ -33,6 +34,43 @@ impl Display for StorageEndpoint {
 
 impl StorageEndpoint {
 
+	/// Every storage endpoint, starting with the default endpoint in Falkenstein
+	pub fn all() -> Vec<StorageEndpoint> {
+		return vec![
+			StorageEndpoint::Falkenstein,
+			StorageEndpoint::London,
+			StorageEndpoint::NewYork,
+			StorageEndpoint::LosAngeles,
+			StorageEndpoint::SingaPore,
+			StorageEndpoint::Stockholm,
+			StorageEndpoint::SaoPaulo,
+			StorageEndpoint::Johannesburg,
+			StorageEndpoint::Sydney,
+		];
+	}
+
+	/// The region code used for the main and replication regions of a storage zone e.g. DE, NY
+	pub fn region_code(&self) -> &'static str {
+		return match self {
+			StorageEndpoint::Falkenstein => "DE",
+			StorageEndpoint::London => "UK",
+			StorageEndpoint::NewYork => "NY",
+			StorageEndpoint::LosAngeles => "LA",
+			StorageEndpoint::SingaPore => "SG",
+			StorageEndpoint::Stockholm => "SE",
+			StorageEndpoint::SaoPaulo => "BR",
+			StorageEndpoint::Johannesburg => "JH",
+			StorageEndpoint::Sydney => "SYD",
+		};
+	}
+
+	pub fn from_region_code(region_code: &str) -> Result<StorageEndpoint, Error> {
+		let used_region_code = region_code.trim().to_uppercase();
+		return StorageEndpoint::all().into_iter()
+			.find(|storage_endpoint| storage_endpoint.region_code() == used_region_code)
+			.ok_or_else(|| Error::new_from_message(&format!("Invalid Region Code - Provided {}", region_code)));
+	}
+
 	pub fn url(&self) -> String {
 		return format!("https://{}", self.to_string());
 	}
@@ -87,4 +125,14 @@ mod storage_endpoints_tests {
 			}
 		}
 	}
+
+	#[test]
+	fn test_region_codes() {
+		for storage_endpoint in StorageEndpoint::all().into_iter() {
+			let test_storage_endpoint_result = StorageEndpoint::from_region_code(storage_endpoint.region_code());
+			assert!(test_storage_endpoint_result.is_ok_and(|test_storage_endpoint| test_storage_endpoint == storage_endpoint));
+		}
+		assert!(StorageEndpoint::from_region_code(" ny").is_ok_and(|storage_endpoint| storage_endpoint == StorageEndpoint::NewYork));
+		assert!(StorageEndpoint::from_region_code("XX").is_err());
+	}
 }
@@ -1,7 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
-use super::{serialize_datetime, deserialize_datetime, pullzone::PullZone};
+use super::{serialize_datetime, deserialize_datetime, pullzone::PullZone, storageendpoint::StorageEndpoint};
 
 
 #[derive(Debug, Serialize_repr, Deserialize_repr, Clone)]
@@ -59,4 +59,20 @@ pub struct StorageZone {
 	pub price_override: f64,
 	// The Storage Zone specific pricing discount
 	pub discount: i32,
-}
\ No newline at end of file
+}
+impl StorageZone {
+
+	/// The storage endpoints of the main region followed by the replication regions.
+	/// Regions without a known storage endpoint are skipped
+	pub fn endpoints(&self) -> Vec<StorageEndpoint> {
+		let mut storage_endpoints: Vec<StorageEndpoint> = Vec::new();
+		for region_code in std::iter::once(&self.region).chain(self.replication_regions.iter()) {
+			if let Ok(storage_endpoint) = StorageEndpoint::from_region_code(region_code) {
+				if !storage_endpoints.contains(&storage_endpoint) {
+					storage_endpoints.push(storage_endpoint);
+				}
+			}
+		}
+		return storage_endpoints;
+	}
+}
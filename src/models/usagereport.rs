@@ -0,0 +1,89 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::{errors::Error, models::{serialize_datetime, serialize_datetime_option}};
+
+const USAGE_REPORT_CSV_HEADER: [&str; 7] = [
+	"path",
+	"depth",
+	"total_bytes",
+	"file_count",
+	"oldest_last_changed",
+	"newest_last_changed",
+	"largest_files",
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct UsageFile {
+	pub path: String,
+	pub length: u64,
+}
+
+/// The usage of a single directory, including every file in its subdirectories
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct DirectoryUsage {
+	pub path: String,
+	// The depth relative to the prefix of the report, which has depth 0
+	pub depth: usize,
+	pub total_bytes: u64,
+	pub file_count: u64,
+	// Sorted by length in descending order
+	pub largest_files: Vec<UsageFile>,
+	#[serde(serialize_with = "serialize_datetime_option")]
+	pub oldest_last_changed: Option<DateTime<Utc>>,
+	#[serde(serialize_with = "serialize_datetime_option")]
+	pub newest_last_changed: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct UsageReport {
+	pub prefix: String,
+	#[serde(serialize_with = "serialize_datetime")]
+	pub generated_at: DateTime<Utc>,
+	// Sorted by path, so a directory is always followed by its subdirectories
+	pub directories: Vec<DirectoryUsage>,
+}
+
+impl UsageReport {
+
+	pub fn to_json(&self) -> Result<String, Error> {
+		return serde_json::to_string_pretty(self)
+			.map_err(|serialize_error| Error::new_from_message(&format!("Failed Serializing Usage Report - Error: {}", serialize_error)));
+	}
+
+	/// Writes a row per directory. The largest files are joined into a single column
+	/// as path:length separated by ;
+	pub fn write_csv<W: std::io::Write>(&self, writer: W) -> Result<(), Error> {
+		let mut csv_writer = csv::Writer::from_writer(writer);
+		csv_writer.write_record(USAGE_REPORT_CSV_HEADER)
+			.map_err(|csv_error| Error::new_from_message(&format!("Failed Writing Usage Report - Error: {}", csv_error)))?;
+		for directory_usage in self.directories.iter() {
+			let largest_files = directory_usage.largest_files.iter()
+				.map(|usage_file| format!("{}:{}", usage_file.path, usage_file.length))
+				.collect::<Vec<String>>()
+				.join(";");
+			csv_writer.write_record([
+				directory_usage.path.clone(),
+				directory_usage.depth.to_string(),
+				directory_usage.total_bytes.to_string(),
+				directory_usage.file_count.to_string(),
+				directory_usage.oldest_last_changed.map(|last_changed| last_changed.to_rfc3339()).unwrap_or_default(),
+				directory_usage.newest_last_changed.map(|last_changed| last_changed.to_rfc3339()).unwrap_or_default(),
+				largest_files,
+			]).map_err(|csv_error| Error::new_from_message(&format!("Failed Writing Usage Report - Error: {}", csv_error)))?;
+		}
+		csv_writer.flush()
+			.map_err(|flush_error| Error::new_from_message(&format!("Failed Writing Usage Report - Error: {}", flush_error)))?;
+		return Ok(());
+	}
+
+	pub fn to_csv(&self) -> Result<String, Error> {
+		let mut csv_buffer: Vec<u8> = Vec::new();
+		self.write_csv(&mut csv_buffer)?;
+		return String::from_utf8(csv_buffer)
+			.map_err(|utf8_error| Error::new_from_message(&format!("Failed Writing Usage Report - Error: {}", utf8_error)));
+	}
+}
@@ -0,0 +1,277 @@
+use std::fmt::Display;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+
+use crate::errors::Error;
+
+// Characters which must be encoded inside a single path segment of a storage URL
+const PATH_SEGMENT_ENCODE_SET: &AsciiSet = &CONTROLS
+	.add(b' ')
+	.add(b'"')
+	.add(b'#')
+	.add(b'%')
+	.add(b'<')
+	.add(b'>')
+	.add(b'?')
+	.add(b'`')
+	.add(b'{')
+	.add(b'}')
+	.add(b'[')
+	.add(b']')
+	.add(b'\\')
+	.add(b'^')
+	.add(b'|');
+
+/// A normalized path inside a storage zone.
+///
+/// The path is always relative to the root of the storage zone, so leading ./ and /
+/// are removed, repeated / are collapsed and . segments are dropped. Traversal with ..
+/// is rejected. Unlike a plain string a RemotePath knows whether it refers to a file or
+/// a directory. When parsing, a trailing / marks a directory, which is also how
+/// Bunnystorage distinguishes the two.
+///
+/// # Examples
+/// ```
+/// use bunnystorage_rs::remotepath::RemotePath;
+/// let directory = RemotePath::parse("./images/")?;
+/// let image = directory.join("summer 2024/beach#1.jpg")?;
+/// assert_eq!(image.to_string(), "images/summer 2024/beach#1.jpg");
+/// assert_eq!(image.to_url_path(), "images/summer%202024/beach%231.jpg");
+/// # Ok::<(), bunnystorage_rs::errors::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RemotePath {
+	segments: Vec<String>,
+	is_directory: bool,
+}
+
+impl RemotePath {
+
+	fn normalize_segments(path: &str) -> Result<Vec<String>, Error> {
+		let mut segments: Vec<String> = Vec::new();
+		for segment in path.trim().split('/') {
+			match segment {
+				"" | "." => continue,
+				".." => return Err(Error::new_from_message(&format!("Invalid Remote Path - Traversal with .. is not allowed. Provided: {}", path))),
+				_ => segments.push(segment.to_string()),
+			}
+		}
+		return Ok(segments);
+	}
+
+	/// The root directory of the storage zone
+	pub fn root() -> RemotePath {
+		return RemotePath{
+			segments: Vec::new(),
+			is_directory: true,
+		};
+	}
+
+	/// Parses a path where a trailing / marks a directory and anything else a file
+	pub fn parse(path: &str) -> Result<RemotePath, Error> {
+		let trimmed_path = path.trim();
+		if trimmed_path.is_empty() {
+			return Err(Error::new_from_message("Invalid Remote Path - Must not be empty"));
+		}
+		let segments = Self::normalize_segments(trimmed_path)?;
+		let is_directory = trimmed_path.ends_with('/') || segments.is_empty();
+		return Ok(RemotePath{
+			segments,
+			is_directory,
+		});
+	}
+
+	/// Parses a path which must refer to a file, so it may not have a trailing /
+	pub fn file(path: &str) -> Result<RemotePath, Error> {
+		if path.trim().ends_with('/') {
+			return Err(Error::new_from_message(&format!("Invalid Filepath - Provided: {}. Has a trailing /. Trying to use a directory?", path)));
+		}
+		let segments = Self::normalize_segments(path)?;
+		if segments.is_empty() {
+			return Err(Error::new_from_message(&format!("Invalid Filepath - Provided: {}. Missing file name", path)));
+		}
+		return Ok(RemotePath{
+			segments,
+			is_directory: false,
+		});
+	}
+
+	/// Parses a path which is always treated as a directory, regardless of a trailing /
+	/// An empty path or / refers to the root of the storage zone
+	pub fn directory(path: &str) -> Result<RemotePath, Error> {
+		let segments = Self::normalize_segments(path)?;
+		return Ok(RemotePath{
+			segments,
+			is_directory: true,
+		});
+	}
+
+	pub fn is_root(&self) -> bool {
+		return self.segments.is_empty();
+	}
+
+	pub fn is_directory(&self) -> bool {
+		return self.is_directory;
+	}
+
+	pub fn is_file(&self) -> bool {
+		return !self.is_directory;
+	}
+
+	/// The individual, unencoded, segments of the path
+	pub fn segments(&self) -> &[String] {
+		return &self.segments;
+	}
+
+	/// The last segment of the path, which for a directory is the directory name.
+	/// The root has no name
+	pub fn file_name(&self) -> Option<&str> {
+		return self.segments.last().map(|segment| segment.as_str());
+	}
+
+	/// The extension of a file. Directories do not have an extension
+	pub fn extension(&self) -> Option<&str> {
+		if self.is_directory {
+			return None;
+		}
+		return self.file_name()
+			.and_then(|file_name| file_name.rsplit_once('.'))
+			.filter(|(file_stem, _)| !file_stem.is_empty())
+			.map(|(_, extension)| extension);
+	}
+
+	/// The directory containing this path. The root has no parent
+	pub fn parent(&self) -> Option<RemotePath> {
+		if self.is_root() {
+			return None;
+		}
+		return Some(RemotePath{
+			segments: self.segments[..self.segments.len() - 1].to_vec(),
+			is_directory: true,
+		});
+	}
+
+	/// Appends a relative path to a directory. The result is a file or directory
+	/// depending on a trailing / on the relative path
+	pub fn join(&self, relative_path: &str) -> Result<RemotePath, Error> {
+		if !self.is_directory {
+			return Err(Error::new_from_message(&format!("Invalid Remote Path - Cannot join onto the file {}", self)));
+		}
+		let relative_remote_path = Self::parse(relative_path)?;
+		let mut segments = self.segments.clone();
+		segments.extend(relative_remote_path.segments);
+		return Ok(RemotePath{
+			segments,
+			is_directory: relative_remote_path.is_directory,
+		});
+	}
+
+	/// The same path but treated as a directory
+	pub fn as_directory(&self) -> RemotePath {
+		return RemotePath{
+			segments: self.segments.clone(),
+			is_directory: true,
+		};
+	}
+
+	/// Checks if this path is located below, or is equal to, the provided directory
+	pub fn starts_with(&self, directory: &RemotePath) -> bool {
+		return self.segments.starts_with(&directory.segments);
+	}
+
+	/// The remainder of the path after the provided directory, keeping the trailing /
+	/// of directories. Returns None if the path is not located below the directory
+	pub fn strip_prefix(&self, directory: &RemotePath) -> Option<String> {
+		if !self.starts_with(directory) {
+			return None;
+		}
+		let mut stripped_path = self.segments[directory.segments.len()..].join("/");
+		if self.is_directory && !stripped_path.is_empty() {
+			stripped_path.push('/');
+		}
+		return Some(stripped_path);
+	}
+
+	/// The percent encoded path for use in a storage URL. Directories keep their trailing /
+	pub fn to_url_path(&self) -> String {
+		let mut url_path = self.segments.iter()
+			.map(|segment| utf8_percent_encode(segment, PATH_SEGMENT_ENCODE_SET).to_string())
+			.collect::<Vec<String>>()
+			.join("/");
+		if self.is_directory && !self.is_root() {
+			url_path.push('/');
+		}
+		return url_path;
+	}
+}
+
+impl Display for RemotePath {
+
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let mut displayed_path = self.segments.join("/");
+		if self.is_directory && !self.is_root() {
+			displayed_path.push('/');
+		}
+		return write!(f, "{}", displayed_path);
+	}
+}
+
+impl TryFrom<&str> for RemotePath {
+	type Error = Error;
+
+	fn try_from(path: &str) -> Result<Self, Self::Error> {
+		return RemotePath::parse(path);
+	}
+}
+
+#[cfg(test)]
+mod remote_path_tests {
+	use super::*;
+
+	#[test]
+	fn test_parse_normalizes() {
+		let test_paths = [
+			("./images/logo.png", "images/logo.png", false),
+			("/images//logo.png", "images/logo.png", false),
+			("images/./icons/", "images/icons/", true),
+			("/", "", true),
+			("  /images/ ", "images/", true),
+		];
+		for (test_path, expected_path, expected_directory) in test_paths.iter() {
+			let remote_path_result = RemotePath::parse(test_path);
+			assert!(remote_path_result.is_ok());
+			let remote_path = remote_path_result.unwrap();
+			assert_eq!(remote_path.to_string(), *expected_path);
+			assert_eq!(remote_path.is_directory(), *expected_directory);
+		}
+		assert!(RemotePath::parse("").is_err());
+		assert!(RemotePath::parse("images/../secret.txt").is_err());
+		assert!(RemotePath::file("images/").is_err());
+		assert!(RemotePath::file("/").is_err());
+		assert!(RemotePath::directory("images").is_ok_and(|remote_path| remote_path.is_directory()));
+	}
+
+	#[test]
+	fn test_join_parent_and_file_name() {
+		let directory = RemotePath::parse("images/").unwrap();
+		let joined_file = directory.join("icons/logo.png").unwrap();
+		assert_eq!(joined_file.to_string(), "images/icons/logo.png");
+		assert_eq!(joined_file.file_name(), Some("logo.png"));
+		assert_eq!(joined_file.extension(), Some("png"));
+		assert_eq!(joined_file.parent().unwrap().to_string(), "images/icons/");
+		assert_eq!(joined_file.strip_prefix(&directory), Some("icons/logo.png".to_string()));
+		assert!(joined_file.join("other.png").is_err());
+		assert!(directory.join("../other.png").is_err());
+		assert!(RemotePath::root().parent().is_none());
+		assert_eq!(RemotePath::parse(".hidden").unwrap().extension(), None);
+	}
+
+	#[test]
+	fn test_to_url_path() {
+		let remote_path = RemotePath::parse("my files/report #1?.pdf").unwrap();
+		assert_eq!(remote_path.to_url_path(), "my%20files/report%20%231%3F.pdf");
+		let unicode_path = RemotePath::parse("bilder/æøå/").unwrap();
+		assert_eq!(unicode_path.to_url_path(), "bilder/%C3%A6%C3%B8%C3%A5/");
+		assert_eq!(RemotePath::root().to_url_path(), "");
+	}
+}
@@ -0,0 +1,103 @@
+use std::ops::Range;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::{errors::Error, models::file::FileChecksum, remotepath::RemotePath};
+
+pub mod bunny;
+pub mod local;
+pub mod memory;
+#[cfg(feature = "object-store")]
+pub mod objectstore;
+
+/// The metadata of a single object, which every backend is able to provide
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectMeta {
+	pub path: RemotePath,
+	// The size of the object in bytes
+	pub size: u64,
+	pub last_modified: Option<DateTime<Utc>>,
+	// Not every backend calculates a checksum
+	pub checksum: Option<FileChecksum>,
+}
+
+/// A backend agnostic interface for storing objects.
+///
+/// This allows for writing code against Bunnystorage while running it against a local directory
+/// during development or an in-memory store in tests. The trait is implemented by BunnyCDNClient,
+/// LocalObjectStore and MemoryObjectStore.
+///
+/// Paths are always relative to the root of the store. Objects are files, so paths passed to
+/// put, get and get_range must refer to files, whereas list and delete also accept directories.
+#[async_trait]
+pub trait ObjectStore: Send + Sync {
+
+	/// Stores an object, replacing any existing object at the same path
+	async fn put(&self, path: &RemotePath, data: Vec<u8>) -> Result<(), Error>;
+
+	/// Retrieves the entire content of an object
+	async fn get(&self, path: &RemotePath) -> Result<Vec<u8>, Error>;
+
+	/// Retrieves part of an object. The range is exclusive and cut off at the end of the object
+	async fn get_range(&self, path: &RemotePath, range: Range<u64>) -> Result<Vec<u8>, Error>;
+
+	/// Lists every object below the provided directory, including those in subdirectories
+	async fn list(&self, prefix: &RemotePath) -> Result<Vec<ObjectMeta>, Error>;
+
+	/// Deletes an object or, when provided a directory, every object below it.
+	/// The root of the store cannot be deleted
+	async fn delete(&self, path: &RemotePath) -> Result<(), Error>;
+
+	/// Retrieves the metadata of an object. Returns None if it does not exist
+	async fn stat(&self, path: &RemotePath) -> Result<Option<ObjectMeta>, Error>;
+}
+
+pub(crate) fn check_object_path(path: &RemotePath) -> Result<(), Error> {
+	if path.is_directory() {
+		return Err(Error::new_from_message(&format!("Invalid Object Path - {} is a directory", path)));
+	}
+	return Ok(());
+}
+
+pub(crate) fn check_delete_path(path: &RemotePath) -> Result<(), Error> {
+	if path.is_root() {
+		return Err(Error::new_from_message("Invalid Object Path - Deleting the root of the store is not allowed"));
+	}
+	return Ok(());
+}
+
+pub(crate) fn slice_object_range(data: &[u8], range: Range<u64>) -> Vec<u8> {
+	let data_length = data.len() as u64;
+	let range_start = range.start.min(data_length) as usize;
+	let range_end = range.end.min(data_length).max(range.start.min(data_length)) as usize;
+	return data[range_start..range_end].to_vec();
+}
+
+#[cfg(test)]
+mod store_tests {
+	use crate::client::{BunnyCDNClient, BunnyCDNClientConfig};
+	use super::{local::LocalObjectStore, memory::MemoryObjectStore, *};
+
+	#[tokio::test]
+	async fn test_reject_deleting_root() {
+		let test_root = std::env::temp_dir().join(format!("bunnystorage-{}", uuid::Uuid::new_v4()));
+		let test_path = RemotePath::parse("reports/summary.csv").unwrap();
+		let local_object_store = LocalObjectStore::new(&test_root);
+		let memory_object_store = MemoryObjectStore::new();
+		assert!(local_object_store.put(&test_path, b"a,b\n".to_vec()).await.is_ok());
+		assert!(memory_object_store.put(&test_path, b"a,b\n".to_vec()).await.is_ok());
+		let bunny_client = BunnyCDNClient::new(BunnyCDNClientConfig{
+			write_password: Some("write_password".to_string()),
+			..BunnyCDNClientConfig::new_offline()
+		}).unwrap();
+		let object_stores: Vec<&dyn ObjectStore> = vec![&local_object_store, &memory_object_store, &bunny_client];
+		for object_store in object_stores.into_iter() {
+			let delete_root_result = object_store.delete(&RemotePath::root()).await;
+			assert!(delete_root_result.is_err_and(|delete_root_error| delete_root_error.message == "Invalid Object Path - Deleting the root of the store is not allowed"));
+		}
+		assert!(local_object_store.stat(&test_path).await.is_ok_and(|object_meta| object_meta.is_some()));
+		assert!(memory_object_store.stat(&test_path).await.is_ok_and(|object_meta| object_meta.is_some()));
+		let _ = tokio::fs::remove_dir_all(&test_root).await;
+	}
+}
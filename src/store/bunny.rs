@@ -0,0 +1,61 @@
+use std::ops::Range;
+
+use async_trait::async_trait;
+
+use crate::{client::BunnyCDNClient, errors::Error, models::file::File, remotepath::RemotePath};
+
+use super::{check_delete_path, check_object_path, ObjectMeta, ObjectStore};
+
+fn object_meta_from_file(file: &File) -> Result<ObjectMeta, Error> {
+	return Ok(ObjectMeta{
+		path: file.to_remote_path()?,
+		size: file.length(),
+		last_modified: Some(file.last_changed()),
+		checksum: file.parsed_checksum(),
+	});
+}
+
+#[async_trait]
+impl ObjectStore for BunnyCDNClient {
+
+	async fn put(&self, path: &RemotePath, data: Vec<u8>) -> Result<(), Error> {
+		check_object_path(path)?;
+		return self.put_remote_file(path, data, None).await;
+	}
+
+	async fn get(&self, path: &RemotePath) -> Result<Vec<u8>, Error> {
+		check_object_path(path)?;
+		return self.download_file_content(&path.to_string()).await;
+	}
+
+	async fn get_range(&self, path: &RemotePath, range: Range<u64>) -> Result<Vec<u8>, Error> {
+		check_object_path(path)?;
+		return self.get_remote_file_range(path, range).await;
+	}
+
+	async fn list(&self, prefix: &RemotePath) -> Result<Vec<ObjectMeta>, Error> {
+		let prefix_files = self.get_files_recursive(&prefix.as_directory().to_string()).await?;
+		let mut object_metas: Vec<ObjectMeta> = Vec::new();
+		for prefix_file in prefix_files.iter() {
+			object_metas.push(object_meta_from_file(prefix_file)?);
+		}
+		return Ok(object_metas);
+	}
+
+	async fn delete(&self, path: &RemotePath) -> Result<(), Error> {
+		check_delete_path(path)?;
+		if path.is_directory() {
+			return self.delete_directory(&path.to_string()).await;
+		}
+		return self.delete_file(&path.to_string()).await;
+	}
+
+	async fn stat(&self, path: &RemotePath) -> Result<Option<ObjectMeta>, Error> {
+		check_object_path(path)?;
+		let remote_file_opt = self.stat_remote_path(path, true).await?;
+		return match remote_file_opt {
+			Some(remote_file) => Ok(Some(object_meta_from_file(&remote_file)?)),
+			None => Ok(None),
+		};
+	}
+}
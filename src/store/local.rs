@@ -0,0 +1,155 @@
+use std::{io::ErrorKind, ops::Range, path::{Path, PathBuf}};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::{errors::Error, remotepath::RemotePath};
+
+use super::{check_delete_path, check_object_path, ObjectMeta, ObjectStore};
+
+fn map_local_io_error(local_io_error: std::io::Error) -> Error {
+	return Error::new_from_message(&format!("Failed Accessing Local Object Store - Error: {}", local_io_error));
+}
+
+/// An ObjectStore backed by a directory on the local filesystem.
+/// Intended for development, where objects can be inspected directly on disk
+pub struct LocalObjectStore {
+	root: PathBuf,
+}
+
+impl LocalObjectStore {
+
+	/// Parameters:
+	/// * root: The directory containing the objects. It is created on the first put
+	pub fn new<P: Into<PathBuf>>(root: P) -> LocalObjectStore {
+		return LocalObjectStore{
+			root: root.into(),
+		};
+	}
+
+	pub fn root(&self) -> &Path {
+		return &self.root;
+	}
+
+	fn get_local_path(&self, path: &RemotePath) -> PathBuf {
+		let mut local_path = self.root.clone();
+		for segment in path.segments().iter() {
+			local_path.push(segment);
+		}
+		return local_path;
+	}
+
+	async fn get_object_meta(&self, path: RemotePath, local_path: &Path) -> Result<ObjectMeta, Error> {
+		let local_metadata = tokio::fs::metadata(local_path).await.map_err(map_local_io_error)?;
+		return Ok(ObjectMeta{
+			path,
+			size: local_metadata.len(),
+			last_modified: local_metadata.modified().ok().map(DateTime::<Utc>::from),
+			checksum: None,
+		});
+	}
+}
+
+#[async_trait]
+impl ObjectStore for LocalObjectStore {
+
+	async fn put(&self, path: &RemotePath, data: Vec<u8>) -> Result<(), Error> {
+		check_object_path(path)?;
+		let local_path = self.get_local_path(path);
+		if let Some(local_directory) = local_path.parent() {
+			tokio::fs::create_dir_all(local_directory).await.map_err(map_local_io_error)?;
+		}
+		tokio::fs::write(&local_path, data).await.map_err(map_local_io_error)?;
+		return Ok(());
+	}
+
+	async fn get(&self, path: &RemotePath) -> Result<Vec<u8>, Error> {
+		check_object_path(path)?;
+		let local_data = tokio::fs::read(self.get_local_path(path)).await.map_err(map_local_io_error)?;
+		return Ok(local_data);
+	}
+
+	async fn get_range(&self, path: &RemotePath, range: Range<u64>) -> Result<Vec<u8>, Error> {
+		check_object_path(path)?;
+		let mut local_file = tokio::fs::File::open(self.get_local_path(path)).await.map_err(map_local_io_error)?;
+		let local_file_length = local_file.metadata().await.map_err(map_local_io_error)?.len();
+		let range_start = range.start.min(local_file_length);
+		let range_end = range.end.min(local_file_length).max(range_start);
+		local_file.seek(std::io::SeekFrom::Start(range_start)).await.map_err(map_local_io_error)?;
+		let mut range_data: Vec<u8> = Vec::with_capacity((range_end - range_start) as usize);
+		local_file.take(range_end - range_start).read_to_end(&mut range_data).await.map_err(map_local_io_error)?;
+		return Ok(range_data);
+	}
+
+	async fn list(&self, prefix: &RemotePath) -> Result<Vec<ObjectMeta>, Error> {
+		let mut object_metas: Vec<ObjectMeta> = Vec::new();
+		let mut pending_directories: Vec<RemotePath> = vec![prefix.as_directory()];
+		while let Some(pending_directory) = pending_directories.pop() {
+			let mut directory_entries = match tokio::fs::read_dir(self.get_local_path(&pending_directory)).await {
+				Ok(directory_entries) => directory_entries,
+				Err(read_dir_error) if read_dir_error.kind() == ErrorKind::NotFound => continue,
+				Err(read_dir_error) => return Err(map_local_io_error(read_dir_error)),
+			};
+			while let Some(directory_entry) = directory_entries.next_entry().await.map_err(map_local_io_error)? {
+				let entry_name = directory_entry.file_name().to_string_lossy().to_string();
+				if directory_entry.file_type().await.map_err(map_local_io_error)?.is_dir() {
+					pending_directories.push(pending_directory.join(&format!("{}/", entry_name))?);
+					continue;
+				}
+				let entry_path = pending_directory.join(&entry_name)?;
+				object_metas.push(self.get_object_meta(entry_path, &directory_entry.path()).await?);
+			}
+		}
+		object_metas.sort_by(|object_meta, other_object_meta| object_meta.path.cmp(&other_object_meta.path));
+		return Ok(object_metas);
+	}
+
+	async fn delete(&self, path: &RemotePath) -> Result<(), Error> {
+		check_delete_path(path)?;
+		let local_path = self.get_local_path(path);
+		let delete_result = match path.is_directory() {
+			true => tokio::fs::remove_dir_all(&local_path).await,
+			false => tokio::fs::remove_file(&local_path).await,
+		};
+		return match delete_result {
+			Err(delete_error) if delete_error.kind() != ErrorKind::NotFound => Err(map_local_io_error(delete_error)),
+			_ => Ok(()),
+		};
+	}
+
+	async fn stat(&self, path: &RemotePath) -> Result<Option<ObjectMeta>, Error> {
+		check_object_path(path)?;
+		let local_path = self.get_local_path(path);
+		return match tokio::fs::metadata(&local_path).await {
+			Ok(local_metadata) if local_metadata.is_file() => Ok(Some(self.get_object_meta(path.clone(), &local_path).await?)),
+			Ok(_) => Ok(None),
+			Err(metadata_error) if metadata_error.kind() == ErrorKind::NotFound => Ok(None),
+			Err(metadata_error) => Err(map_local_io_error(metadata_error)),
+		};
+	}
+}
+
+#[cfg(test)]
+mod local_object_store_tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn test_local_object_store() {
+		let test_root = std::env::temp_dir().join(format!("bunnystorage-{}", uuid::Uuid::new_v4()));
+		let object_store = LocalObjectStore::new(&test_root);
+		let test_path = RemotePath::parse("reports/2024/summary.csv").unwrap();
+		let test_put_result = object_store.put(&test_path, b"a,b\n1,2\n".to_vec()).await;
+		assert!(test_put_result.is_ok());
+		assert!(object_store.get(&test_path).await.is_ok_and(|data| data == b"a,b\n1,2\n"));
+		assert!(object_store.get_range(&test_path, 2..5).await.is_ok_and(|data| data == b"b\n1"));
+		assert!(object_store.get_range(&test_path, 6..100).await.is_ok_and(|data| data == b"2\n"));
+		assert!(object_store.stat(&test_path).await.is_ok_and(|object_meta| object_meta.is_some_and(|object_meta| object_meta.size == 8)));
+		let test_list_result = object_store.list(&RemotePath::parse("reports/").unwrap()).await;
+		assert!(test_list_result.is_ok_and(|object_metas| object_metas.len() == 1 && object_metas[0].path == test_path));
+		let test_delete_result = object_store.delete(&RemotePath::parse("reports/").unwrap()).await;
+		assert!(test_delete_result.is_ok());
+		assert!(object_store.stat(&test_path).await.is_ok_and(|object_meta| object_meta.is_none()));
+		let _ = tokio::fs::remove_dir_all(&test_root).await;
+	}
+}
@@ -0,0 +1,119 @@
+use std::{collections::BTreeMap, ops::Range, sync::RwLock};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+
+use crate::{errors::Error, models::file::FileChecksum, remotepath::RemotePath};
+
+use super::{check_delete_path, check_object_path, slice_object_range, ObjectMeta, ObjectStore};
+
+struct MemoryObject {
+	data: Vec<u8>,
+	meta: ObjectMeta,
+}
+
+/// An ObjectStore keeping every object in memory. Intended for tests
+#[derive(Default)]
+pub struct MemoryObjectStore {
+	objects: RwLock<BTreeMap<RemotePath, MemoryObject>>,
+}
+
+impl MemoryObjectStore {
+
+	pub fn new() -> MemoryObjectStore {
+		return MemoryObjectStore::default();
+	}
+
+	fn lock_error(&self) -> Error {
+		return Error::new_from_message("Failed Accessing Memory Object Store - Lock poisoned");
+	}
+}
+
+#[async_trait]
+impl ObjectStore for MemoryObjectStore {
+
+	async fn put(&self, path: &RemotePath, data: Vec<u8>) -> Result<(), Error> {
+		check_object_path(path)?;
+		let object_meta = ObjectMeta{
+			path: path.clone(),
+			size: data.len() as u64,
+			last_modified: Some(Utc::now()),
+			checksum: Some(FileChecksum::Sha256(Sha256::digest(&data).into())),
+		};
+		let mut objects = self.objects.write().map_err(|_| self.lock_error())?;
+		objects.insert(path.clone(), MemoryObject{
+			data,
+			meta: object_meta,
+		});
+		return Ok(());
+	}
+
+	async fn get(&self, path: &RemotePath) -> Result<Vec<u8>, Error> {
+		check_object_path(path)?;
+		let objects = self.objects.read().map_err(|_| self.lock_error())?;
+		return match objects.get(path) {
+			Some(memory_object) => Ok(memory_object.data.clone()),
+			None => Err(Error::new_from_message(&format!("Object not found - {}", path))),
+		};
+	}
+
+	async fn get_range(&self, path: &RemotePath, range: Range<u64>) -> Result<Vec<u8>, Error> {
+		check_object_path(path)?;
+		let objects = self.objects.read().map_err(|_| self.lock_error())?;
+		return match objects.get(path) {
+			Some(memory_object) => Ok(slice_object_range(&memory_object.data, range)),
+			None => Err(Error::new_from_message(&format!("Object not found - {}", path))),
+		};
+	}
+
+	async fn list(&self, prefix: &RemotePath) -> Result<Vec<ObjectMeta>, Error> {
+		let used_prefix = prefix.as_directory();
+		let objects = self.objects.read().map_err(|_| self.lock_error())?;
+		let object_metas = objects.values()
+			.filter(|memory_object| memory_object.meta.path.starts_with(&used_prefix))
+			.map(|memory_object| memory_object.meta.clone())
+			.collect();
+		return Ok(object_metas);
+	}
+
+	async fn delete(&self, path: &RemotePath) -> Result<(), Error> {
+		check_delete_path(path)?;
+		let mut objects = self.objects.write().map_err(|_| self.lock_error())?;
+		if path.is_directory() {
+			objects.retain(|object_path, _| !object_path.starts_with(path));
+			return Ok(());
+		}
+		objects.remove(path);
+		return Ok(());
+	}
+
+	async fn stat(&self, path: &RemotePath) -> Result<Option<ObjectMeta>, Error> {
+		check_object_path(path)?;
+		let objects = self.objects.read().map_err(|_| self.lock_error())?;
+		return Ok(objects.get(path).map(|memory_object| memory_object.meta.clone()));
+	}
+}
+
+#[cfg(test)]
+mod memory_object_store_tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn test_memory_object_store() {
+		let object_store = MemoryObjectStore::new();
+		let test_path = RemotePath::parse("reports/2024/summary.csv").unwrap();
+		let test_put_result = object_store.put(&test_path, b"a,b\n1,2\n".to_vec()).await;
+		assert!(test_put_result.is_ok());
+		assert!(object_store.get(&test_path).await.is_ok_and(|data| data == b"a,b\n1,2\n"));
+		assert!(object_store.get_range(&test_path, 2..5).await.is_ok_and(|data| data == b"b\n1"));
+		assert!(object_store.get_range(&test_path, 6..100).await.is_ok_and(|data| data == b"2\n"));
+		assert!(object_store.stat(&test_path).await.is_ok_and(|object_meta| object_meta.is_some_and(|object_meta| object_meta.size == 8)));
+		let test_list_result = object_store.list(&RemotePath::parse("reports/").unwrap()).await;
+		assert!(test_list_result.is_ok_and(|object_metas| object_metas.len() == 1));
+		let test_delete_result = object_store.delete(&RemotePath::parse("reports/").unwrap()).await;
+		assert!(test_delete_result.is_ok());
+		assert!(object_store.stat(&test_path).await.is_ok_and(|object_meta| object_meta.is_none()));
+		assert!(object_store.get(&test_path).await.is_err());
+	}
+}
@@ -0,0 +1,344 @@
+use std::{fmt::{Debug, Display}, ops::Range, sync::Arc};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{stream::{self, BoxStream}, StreamExt, TryStreamExt};
+use object_store::{
+	path::Path, Attribute, GetOptions, GetResult, GetResultPayload, ListResult, MultipartUpload, ObjectMeta,
+	PutMode, PutMultipartOptions, PutOptions, PutPayload, PutPayloadMut, PutResult, UploadPart,
+};
+use sha2::{Digest, Sha256};
+
+use crate::{client::{BunnyCDNClient, BunnyCDNDataOptions}, errors::Error, models::file::{File, FileChecksum}, remotepath::RemotePath};
+
+const BUNNY_OBJECT_STORE_NAME: &str = "BunnyStorage";
+
+fn map_store_error(error: Error) -> object_store::Error {
+	return object_store::Error::Generic{
+		store: BUNNY_OBJECT_STORE_NAME,
+		source: Box::new(error),
+	};
+}
+
+fn map_not_found_error(location: &Path) -> object_store::Error {
+	return object_store::Error::NotFound{
+		path: location.to_string(),
+		source: Box::new(Error::new_from_message(&format!("Object not found - {}", location))),
+	};
+}
+
+fn map_not_supported_error(message: &str) -> object_store::Error {
+	return object_store::Error::NotSupported{
+		source: Box::new(Error::new_from_message(message)),
+	};
+}
+
+fn get_remote_filepath(location: &Path) -> object_store::Result<RemotePath> {
+	return RemotePath::file(location.as_ref()).map_err(map_store_error);
+}
+
+fn get_remote_directory(prefix: Option<&Path>) -> object_store::Result<RemotePath> {
+	return match prefix {
+		Some(prefix) => RemotePath::directory(prefix.as_ref()).map_err(map_store_error),
+		None => Ok(RemotePath::root()),
+	};
+}
+
+fn get_object_location(remote_path: &RemotePath) -> Path {
+	return Path::from_iter(remote_path.segments().iter().map(|segment| segment.as_str()));
+}
+
+fn get_object_meta(file: &File) -> object_store::Result<ObjectMeta> {
+	let remote_path = file.to_remote_path().map_err(map_store_error)?;
+	return Ok(ObjectMeta{
+		location: get_object_location(&remote_path),
+		last_modified: file.last_changed(),
+		size: file.length(),
+		e_tag: file.parsed_checksum().map(|checksum| checksum.to_hex()),
+		version: None,
+	});
+}
+
+fn get_put_data_options(attributes: &object_store::Attributes) -> Option<BunnyCDNDataOptions> {
+	return attributes.get(&Attribute::ContentType).map(|content_type| BunnyCDNDataOptions{
+		content_type: Some(content_type.to_string()),
+		..Default::default()
+	});
+}
+
+/// Exposes a storage zone through the ObjectStore trait of the object_store crate,
+/// so it can be used by e.g. DataFusion, Polars and the Arrow Parquet readers.
+///
+/// Bunnystorage has no conditional writes, so PutMode::Create, PutMode::Update and
+/// copy_if_not_exists return Error::NotSupported. Multipart uploads are buffered in
+/// memory and uploaded in a single request on complete.
+/// The e_tag of an object is its SHA256 checksum, if Bunnystorage has calculated it
+///
+/// # Examples
+/// ```no_run
+/// use std::sync::Arc;
+/// use bunnystorage_rs::{client::BunnyCDNClient, store::objectstore::BunnyObjectStore};
+/// use object_store::{path::Path, ObjectStore};
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let object_store = BunnyObjectStore::new(Arc::new(BunnyCDNClient::new_from_env()?));
+/// let footer = object_store.get_range(&Path::from("data/events.parquet"), 0..8).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct BunnyObjectStore {
+	client: Arc<BunnyCDNClient>,
+}
+
+impl BunnyObjectStore {
+
+	pub fn new(client: Arc<BunnyCDNClient>) -> BunnyObjectStore {
+		return BunnyObjectStore{
+			client,
+		};
+	}
+
+	pub fn client(&self) -> &BunnyCDNClient {
+		return &self.client;
+	}
+
+	async fn head_remote_file(&self, location: &Path) -> object_store::Result<ObjectMeta> {
+		let remote_filepath = get_remote_filepath(location)?;
+		let remote_file_opt = self.client.stat_remote_path(&remote_filepath, true).await.map_err(map_store_error)?;
+		return match remote_file_opt {
+			Some(remote_file) => get_object_meta(&remote_file),
+			None => Err(map_not_found_error(location)),
+		};
+	}
+}
+
+impl Display for BunnyObjectStore {
+
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		return write!(f, "{}({})", BUNNY_OBJECT_STORE_NAME, self.client.storage_zone_name());
+	}
+}
+
+impl Debug for BunnyObjectStore {
+
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		return f.debug_struct("BunnyObjectStore")
+			.field("storage_zone_name", &self.client.storage_zone_name())
+			.finish();
+	}
+}
+
+#[async_trait]
+impl object_store::ObjectStore for BunnyObjectStore {
+
+	async fn put_opts(&self, location: &Path, payload: PutPayload, opts: PutOptions) -> object_store::Result<PutResult> {
+		if opts.mode != PutMode::Overwrite {
+			return Err(map_not_supported_error("Bunnystorage does not support conditional writes"));
+		}
+		let remote_filepath = get_remote_filepath(location)?;
+		let put_data = Bytes::from(payload);
+		let put_checksum = FileChecksum::Sha256(Sha256::digest(&put_data).into());
+		let put_data_options = get_put_data_options(&opts.attributes);
+		self.client.put_remote_file(&remote_filepath, put_data, put_data_options.as_ref()).await.map_err(map_store_error)?;
+		return Ok(PutResult{
+			e_tag: Some(put_checksum.to_hex()),
+			version: None,
+		});
+	}
+
+	async fn put_multipart_opts(&self, location: &Path, opts: PutMultipartOptions) -> object_store::Result<Box<dyn MultipartUpload>> {
+		return Ok(Box::new(BunnyMultipartUpload{
+			object_store: self.clone(),
+			location: location.clone(),
+			parts: PutPayloadMut::new(),
+			opts: PutOptions{
+				attributes: opts.attributes,
+				..Default::default()
+			},
+		}));
+	}
+
+	async fn get_opts(&self, location: &Path, options: GetOptions) -> object_store::Result<GetResult> {
+		let object_meta = self.head_remote_file(location).await?;
+		options.check_preconditions(&object_meta)?;
+		let remote_filepath = get_remote_filepath(location)?;
+		if options.head {
+			return Ok(GetResult{
+				payload: GetResultPayload::Stream(stream::empty().boxed()),
+				meta: object_meta,
+				range: 0..0,
+				attributes: Default::default(),
+			});
+		}
+		let full_range = 0..object_meta.size;
+		let used_range = match &options.range {
+			Some(get_range) => get_range.as_range(object_meta.size)
+				.map_err(|get_range_error| map_store_error(Error::new_from_message(&format!("Invalid Range - {}", get_range_error))))?,
+			None => full_range.clone(),
+		};
+		if used_range != full_range {
+			let range_data = self.client.find_remote_file_range(&remote_filepath, used_range.clone()).await.map_err(map_store_error)?
+				.ok_or_else(|| map_not_found_error(location))?;
+			return Ok(GetResult{
+				payload: GetResultPayload::Stream(stream::once(async move { Ok(Bytes::from(range_data)) }).boxed()),
+				meta: object_meta,
+				range: used_range,
+				attributes: Default::default(),
+			});
+		}
+		let download_file_response = self.client.open_remote_file_response(&remote_filepath).await.map_err(map_store_error)?;
+		let download_file_stream = download_file_response.bytes_stream()
+			.map_err(|stream_error| map_store_error(Error::new_from_message(&format!("Failed Stream Contents - Error: {}", stream_error))))
+			.boxed();
+		return Ok(GetResult{
+			payload: GetResultPayload::Stream(download_file_stream),
+			meta: object_meta,
+			range: used_range,
+			attributes: Default::default(),
+		});
+	}
+
+	// Skips the initial head request of get_opts, since readers such as Parquet issue many small range requests
+	async fn get_range(&self, location: &Path, range: Range<u64>) -> object_store::Result<Bytes> {
+		let remote_filepath = get_remote_filepath(location)?;
+		let range_data = self.client.find_remote_file_range(&remote_filepath, range).await.map_err(map_store_error)?
+			.ok_or_else(|| map_not_found_error(location))?;
+		return Ok(Bytes::from(range_data));
+	}
+
+	async fn head(&self, location: &Path) -> object_store::Result<ObjectMeta> {
+		return self.head_remote_file(location).await;
+	}
+
+	// Deleting a missing object succeeds, as for the local and memory stores
+	async fn delete(&self, location: &Path) -> object_store::Result<()> {
+		let remote_filepath = get_remote_filepath(location)?;
+		self.client.delete_entry_if_exists(&remote_filepath).await.map_err(map_store_error)?;
+		return Ok(());
+	}
+
+	fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+		let client = self.client.clone();
+		let remote_directory_result = get_remote_directory(prefix);
+		return stream::once(async move {
+			let remote_directory = remote_directory_result?;
+			let remote_files = client.get_files_recursive(&remote_directory.to_string()).await.map_err(map_store_error)?;
+			return Ok::<_, object_store::Error>(stream::iter(remote_files.into_iter().map(|remote_file| get_object_meta(&remote_file))));
+		})
+			.try_flatten()
+			.boxed();
+	}
+
+	async fn list_with_delimiter(&self, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+		let remote_directory = get_remote_directory(prefix)?;
+		let directory_entries = self.client.get_remote_directory_entries(&remote_directory).await.map_err(map_store_error)?;
+		let mut list_result = ListResult{
+			common_prefixes: Vec::new(),
+			objects: Vec::new(),
+		};
+		for directory_entry in directory_entries.iter() {
+			if directory_entry.is_directory() {
+				let remote_path = directory_entry.to_remote_path().map_err(map_store_error)?;
+				list_result.common_prefixes.push(get_object_location(&remote_path));
+			} else {
+				list_result.objects.push(get_object_meta(directory_entry)?);
+			}
+		}
+		return Ok(list_result);
+	}
+
+	async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+		return self.client.copy_file(from.as_ref(), to.as_ref(), None).await.map_err(map_store_error);
+	}
+
+	async fn rename(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+		return self.client.move_file(from.as_ref(), to.as_ref(), None).await.map_err(map_store_error);
+	}
+
+	async fn copy_if_not_exists(&self, _from: &Path, _to: &Path) -> object_store::Result<()> {
+		return Err(map_not_supported_error("Bunnystorage does not support conditional copies"));
+	}
+}
+
+/// Collects the parts of a multipart upload in memory, since Bunnystorage
+/// only supports uploading a file in a single request
+#[derive(Debug)]
+struct BunnyMultipartUpload {
+	object_store: BunnyObjectStore,
+	location: Path,
+	parts: PutPayloadMut,
+	opts: PutOptions,
+}
+
+#[async_trait]
+impl MultipartUpload for BunnyMultipartUpload {
+
+	fn put_part(&mut self, data: PutPayload) -> UploadPart {
+		for part_bytes in data.into_iter() {
+			self.parts.push(part_bytes);
+		}
+		return Box::pin(futures::future::ready(Ok(())));
+	}
+
+	async fn complete(&mut self) -> object_store::Result<PutResult> {
+		let payload = std::mem::take(&mut self.parts).freeze();
+		return object_store::ObjectStore::put_opts(&self.object_store, &self.location, payload, self.opts.clone()).await;
+	}
+
+	async fn abort(&mut self) -> object_store::Result<()> {
+		self.parts = PutPayloadMut::new();
+		return Ok(());
+	}
+}
+
+#[cfg(test)]
+mod bunny_object_store_tests {
+	use object_store::ObjectStore;
+
+	use crate::client::BunnyCDNClientConfig;
+	use super::*;
+
+	#[test]
+	fn test_object_paths() {
+		let remote_filepath = get_remote_filepath(&Path::from("data/2024/events.parquet")).unwrap();
+		assert_eq!(remote_filepath.to_string(), "data/2024/events.parquet");
+		assert_eq!(get_object_location(&remote_filepath), Path::from("data/2024/events.parquet"));
+		assert!(get_remote_directory(None).is_ok_and(|remote_directory| remote_directory.is_root()));
+		assert!(get_remote_directory(Some(&Path::from("data"))).is_ok_and(|remote_directory| remote_directory.to_string() == "data/"));
+	}
+
+	#[tokio::test]
+	async fn test_conditional_writes_not_supported() {
+		let client = BunnyCDNClient::new(BunnyCDNClientConfig::new_offline()).unwrap();
+		let object_store = BunnyObjectStore::new(Arc::new(client));
+		let test_put_options = PutOptions{
+			mode: PutMode::Create,
+			..Default::default()
+		};
+		let test_put_result = object_store.put_opts(&Path::from("data/events.parquet"), PutPayload::from_static(b"PAR1"), test_put_options).await;
+		assert!(matches!(test_put_result, Err(object_store::Error::NotSupported{ .. })));
+		assert_eq!(object_store.to_string(), "BunnyStorage(storage_zone)");
+	}
+
+	#[tokio::test]
+	async fn test_object_store_round_trip() {
+		let client_result: Result<BunnyCDNClient, Error> = BunnyCDNClient::new_from_env();
+		assert!(client_result.is_ok());
+		let object_store = BunnyObjectStore::new(Arc::new(client_result.unwrap()));
+		let test_location = Path::from("tests/files/objectstore/test.bin");
+		let test_put_result = object_store.put(&test_location, PutPayload::from_static(b"0123456789")).await;
+		assert!(test_put_result.is_ok());
+		let test_range_result = object_store.get_range(&test_location, 2..5).await;
+		assert!(test_range_result.is_ok_and(|range_data| range_data.as_ref() == b"234"));
+		let test_head_result = object_store.head(&test_location).await;
+		assert!(test_head_result.is_ok_and(|object_meta| object_meta.size == 10));
+		let test_list_result = object_store.list_with_delimiter(Some(&Path::from("tests/files/objectstore"))).await;
+		assert!(test_list_result.is_ok_and(|list_result| list_result.objects.iter().any(|object_meta| object_meta.location == test_location)));
+		let test_delete_result = object_store.delete(&test_location).await;
+		assert!(test_delete_result.is_ok());
+		// A missing object is reported as NotFound, and deleting it again succeeds
+		let missing_range_result = object_store.get_range(&test_location, 2..5).await;
+		assert!(matches!(missing_range_result, Err(object_store::Error::NotFound{ .. })));
+		assert!(object_store.delete(&test_location).await.is_ok());
+	}
+}
@@ -0,0 +1,284 @@
+use std::collections::BTreeMap;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Duration, Utc};
+use md5::Md5;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+
+use crate::{errors::Error, models::pullzone::PullZone};
+
+const DEFAULT_TOKEN_EXPIRES_IN_SECONDS: i64 = 3600;
+const DIRECTORY_TOKEN_PREFIX: &str = "bcdn_token=";
+const TOKEN_PARAMETER_NAME: &str = "token";
+const EXPIRES_PARAMETER_NAME: &str = "expires";
+const TOKEN_PATH_PARAMETER_NAME: &str = "token_path";
+const TOKEN_COUNTRIES_PARAMETER_NAME: &str = "token_countries";
+const TOKEN_COUNTRIES_BLOCKED_PARAMETER_NAME: &str = "token_countries_blocked";
+
+// Characters which are kept as is in the values of the signed query parameters
+const TOKEN_PARAMETER_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+	.remove(b'-')
+	.remove(b'_')
+	.remove(b'.')
+	.remove(b'~')
+	.remove(b'/');
+
+/// The token authentication schemes supported by pull zones.
+/// See https://docs.bunny.net/docs/cdn-token-authentication for further documentation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TokenScheme {
+	// An MD5 token over the path and expiry
+	Basic,
+	// A SHA256 token which additionally supports path prefixes and country restrictions
+	#[default]
+	Advanced,
+}
+
+pub struct SignUrlParameters {
+	// How long the URL is valid
+	pub expires_in: Duration,
+	// Only allows requests from this IP. Required if the pull zone includes the remote IP in the hash
+	pub remote_ip: Option<String>,
+	// Signs a path prefix instead of the path of the URL, so the token is valid for every file below it.
+	// Only supported by the advanced scheme
+	pub token_path: Option<String>,
+	// Country codes which may use the URL e.g. DE. Only supported by the advanced scheme
+	pub countries_allowed: Vec<String>,
+	// Country codes which may not use the URL. Only supported by the advanced scheme
+	pub countries_blocked: Vec<String>,
+	// Places the token in the path as /bcdn_token=.../path, so relative URLs inside e.g. HLS playlists
+	// inherit the token. Only supported by the advanced scheme
+	pub directory_token: bool,
+}
+
+impl Default for SignUrlParameters {
+
+	fn default() -> Self {
+		return SignUrlParameters{
+			expires_in: Duration::seconds(DEFAULT_TOKEN_EXPIRES_IN_SECONDS),
+			remote_ip: None,
+			token_path: None,
+			countries_allowed: Vec::new(),
+			countries_blocked: Vec::new(),
+			directory_token: false,
+		};
+	}
+}
+
+/// Signs URLs of a pull zone with token authentication enabled
+pub struct UrlSigner {
+	security_key: String,
+	scheme: TokenScheme,
+	require_remote_ip: bool,
+}
+
+impl UrlSigner {
+
+	pub fn new(security_key: &str, scheme: TokenScheme) -> Result<UrlSigner, Error> {
+		if security_key.trim().is_empty() {
+			return Err(Error::new_from_message("Invalid Security Key - Must not be empty"));
+		}
+		return Ok(UrlSigner{
+			security_key: security_key.to_string(),
+			scheme,
+			require_remote_ip: false,
+		});
+	}
+
+	/// Uses the security key of the pull zone. Fails if token authentication is disabled for the pull zone
+	pub fn from_pull_zone(pull_zone: &PullZone, scheme: TokenScheme) -> Result<UrlSigner, Error> {
+		if !pull_zone.zone_security_enabled {
+			return Err(Error::new_from_message(&format!("Token Authentication Is Disabled For Pull Zone {}", pull_zone.name)));
+		}
+		let mut url_signer = UrlSigner::new(&pull_zone.zone_security_key, scheme)?;
+		url_signer.require_remote_ip = pull_zone.zone_security_include_hash_remote_ip;
+		return Ok(url_signer);
+	}
+
+	pub fn scheme(&self) -> TokenScheme {
+		return self.scheme;
+	}
+
+	/// Signs the URL so it is valid from now until the expiry in the parameters
+	pub fn sign_url(&self, url: &str, params: &SignUrlParameters) -> Result<String, Error> {
+		return self.sign_url_until(url, Utc::now() + params.expires_in, params);
+	}
+
+	/// Signs the URL so it is valid until the provided time. The expires_in of the parameters is ignored
+	///
+	/// Parameters:
+	/// * url: The absolute URL on a hostname of the pull zone
+	/// * expires: When the URL stops being valid
+	/// * params: The restrictions of the token
+	pub fn sign_url_until(&self, url: &str, expires: DateTime<Utc>, params: &SignUrlParameters) -> Result<String, Error> {
+		let parsed_url = Url::parse(url)
+			.map_err(|parse_error| Error::new_from_message(&format!("Invalid URL {} - Error: {}", url, parse_error)))?;
+		if self.require_remote_ip && params.remote_ip.is_none() {
+			return Err(Error::new_from_message("Missing Remote IP - The pull zone includes the remote IP in the token"));
+		}
+		let remote_ip = params.remote_ip.as_deref().unwrap_or_default();
+		let expires_timestamp = expires.timestamp();
+		if self.scheme == TokenScheme::Basic {
+			if params.token_path.is_some() || !params.countries_allowed.is_empty() || !params.countries_blocked.is_empty() || params.directory_token {
+				return Err(Error::new_from_message("Unsupported Token Parameters - Path and country restrictions require the advanced scheme"));
+			}
+			let token = self.compute_basic_token(parsed_url.path(), expires_timestamp, remote_ip);
+			let mut signed_url = parsed_url.clone();
+			signed_url.query_pairs_mut()
+				.append_pair(TOKEN_PARAMETER_NAME, &token)
+				.append_pair(EXPIRES_PARAMETER_NAME, &expires_timestamp.to_string());
+			return Ok(signed_url.to_string());
+		}
+		let mut token_parameters: BTreeMap<String, String> = parsed_url.query_pairs()
+			.map(|(parameter_name, parameter_value)| (parameter_name.into_owned(), parameter_value.into_owned()))
+			.collect();
+		if !params.countries_allowed.is_empty() {
+			token_parameters.insert(TOKEN_COUNTRIES_PARAMETER_NAME.to_string(), params.countries_allowed.join(","));
+		}
+		if !params.countries_blocked.is_empty() {
+			token_parameters.insert(TOKEN_COUNTRIES_BLOCKED_PARAMETER_NAME.to_string(), params.countries_blocked.join(","));
+		}
+		if let Some(token_path) = &params.token_path {
+			if !parsed_url.path().starts_with(token_path.as_str()) {
+				return Err(Error::new_from_message(&format!("Invalid Token Path - {} is not a prefix of {}", token_path, parsed_url.path())));
+			}
+			token_parameters.insert(TOKEN_PATH_PARAMETER_NAME.to_string(), token_path.clone());
+		}
+		let signature_path = params.token_path.as_deref().unwrap_or(parsed_url.path());
+		let token = self.compute_advanced_token(signature_path, expires_timestamp, remote_ip, &token_parameters);
+		let encoded_parameters: String = token_parameters.iter()
+			.map(|(parameter_name, parameter_value)| format!("&{}={}", parameter_name, utf8_percent_encode(parameter_value, TOKEN_PARAMETER_ENCODE_SET)))
+			.collect();
+		let url_origin = parsed_url.origin().ascii_serialization();
+		if params.directory_token {
+			return Ok(format!("{}/{}{}{}&{}={}{}", url_origin, DIRECTORY_TOKEN_PREFIX, token, encoded_parameters, EXPIRES_PARAMETER_NAME, expires_timestamp, parsed_url.path()));
+		}
+		return Ok(format!("{}{}?{}={}{}&{}={}", url_origin, parsed_url.path(), TOKEN_PARAMETER_NAME, token, encoded_parameters, EXPIRES_PARAMETER_NAME, expires_timestamp));
+	}
+
+	/// Checks that the URL carries a valid token which has not expired, the same way the pull zone would.
+	/// Meant for tests, the pull zone does not need this to serve signed URLs
+	///
+	/// Parameters:
+	/// * url: The signed URL
+	/// * remote_ip: The IP the request is made from, if the token is bound to an IP
+	/// * now: The time of the request
+	pub fn verify_url(&self, url: &str, remote_ip: Option<&str>, now: DateTime<Utc>) -> Result<(), Error> {
+		let parsed_url = Url::parse(url)
+			.map_err(|parse_error| Error::new_from_message(&format!("Invalid URL {} - Error: {}", url, parse_error)))?;
+		let mut token_parameters: BTreeMap<String, String> = BTreeMap::new();
+		let resource_path = match parsed_url.path().strip_prefix(&format!("/{}", DIRECTORY_TOKEN_PREFIX)) {
+			Some(directory_token_path) => {
+				// The token parameters may contain / themselves, so the resource path starts after the digits of expires
+				let expires_parameter = format!("&{}=", EXPIRES_PARAMETER_NAME);
+				let expires_start = directory_token_path.find(&expires_parameter)
+					.ok_or_else(|| Error::new_from_message("Invalid Signed URL - Missing expires"))? + expires_parameter.len();
+				let resource_path_start = directory_token_path[expires_start..].find(|character: char| !character.is_ascii_digit())
+					.map_or(directory_token_path.len(), |digits_length| expires_start + digits_length);
+				let (directory_token, resource_path) = directory_token_path.split_at(resource_path_start);
+				let directory_token_query = format!("{}={}", TOKEN_PARAMETER_NAME, directory_token);
+				for (parameter_name, parameter_value) in Url::parse(&format!("https://localhost/?{}", directory_token_query)).map_err(|parse_error| Error::new_from_message(&parse_error.to_string()))?.query_pairs() {
+					token_parameters.insert(parameter_name.into_owned(), parameter_value.into_owned());
+				}
+				resource_path.to_string()
+			},
+			None => parsed_url.path().to_string(),
+		};
+		for (parameter_name, parameter_value) in parsed_url.query_pairs() {
+			token_parameters.insert(parameter_name.into_owned(), parameter_value.into_owned());
+		}
+		let token = token_parameters.remove(TOKEN_PARAMETER_NAME)
+			.ok_or_else(|| Error::new_from_message("Invalid Signed URL - Missing token"))?;
+		let expires_timestamp: i64 = token_parameters.remove(EXPIRES_PARAMETER_NAME)
+			.and_then(|expires| expires.parse().ok())
+			.ok_or_else(|| Error::new_from_message("Invalid Signed URL - Missing or invalid expires"))?;
+		if expires_timestamp <= now.timestamp() {
+			return Err(Error::new_from_message(&format!("Expired Signed URL - Expired at {}", expires_timestamp)));
+		}
+		let used_remote_ip = remote_ip.unwrap_or_default();
+		let expected_token = match self.scheme {
+			TokenScheme::Basic => self.compute_basic_token(&resource_path, expires_timestamp, used_remote_ip),
+			TokenScheme::Advanced => {
+				let signature_path = match token_parameters.get(TOKEN_PATH_PARAMETER_NAME) {
+					Some(token_path) if !resource_path.starts_with(token_path.as_str()) => {
+						return Err(Error::new_from_message(&format!("Invalid Signed URL - {} is outside of the token path {}", resource_path, token_path)));
+					},
+					Some(token_path) => token_path.clone(),
+					None => resource_path.clone(),
+				};
+				self.compute_advanced_token(&signature_path, expires_timestamp, used_remote_ip, &token_parameters)
+			},
+		};
+		if token != expected_token {
+			return Err(Error::new_from_message("Invalid Signed URL - Token mismatch"));
+		}
+		return Ok(());
+	}
+
+	fn compute_basic_token(&self, signature_path: &str, expires_timestamp: i64, remote_ip: &str) -> String {
+		let hashable_base = format!("{}{}{}{}", self.security_key, signature_path, expires_timestamp, remote_ip);
+		return URL_SAFE_NO_PAD.encode(Md5::digest(hashable_base.as_bytes()));
+	}
+
+	fn compute_advanced_token(&self, signature_path: &str, expires_timestamp: i64, remote_ip: &str, token_parameters: &BTreeMap<String, String>) -> String {
+		// The parameters are signed sorted by name and without encoding
+		let parameter_data = token_parameters.iter()
+			.map(|(parameter_name, parameter_value)| format!("{}={}", parameter_name, parameter_value))
+			.collect::<Vec<String>>()
+			.join("&");
+		let hashable_base = format!("{}{}{}{}{}", self.security_key, signature_path, expires_timestamp, remote_ip, parameter_data);
+		return URL_SAFE_NO_PAD.encode(Sha256::digest(hashable_base.as_bytes()));
+	}
+}
+
+#[cfg(test)]
+mod url_signing_tests {
+	use super::*;
+
+	#[test]
+	fn test_basic_token() {
+		let url_signer = UrlSigner::new("secret", TokenScheme::Basic).unwrap();
+		let expires = DateTime::from_timestamp(1700000000, 0).unwrap();
+		let signed_url = url_signer.sign_url_until("https://cdn.example.com/images/logo.png", expires, &SignUrlParameters::default()).unwrap();
+		assert_eq!(signed_url, "https://cdn.example.com/images/logo.png?token=lvU7BHImGsH-vPsE38AIYw&expires=1700000000");
+		let before_expiry = DateTime::from_timestamp(1699999999, 0).unwrap();
+		assert!(url_signer.verify_url(&signed_url, None, before_expiry).is_ok());
+		assert!(url_signer.verify_url(&signed_url, None, expires).is_err());
+		assert!(url_signer.verify_url(&signed_url.replace("logo.png", "other.png"), None, before_expiry).is_err());
+		let directory_params = SignUrlParameters{
+			token_path: Some("/images/".to_string()),
+			..Default::default()
+		};
+		assert!(url_signer.sign_url_until("https://cdn.example.com/images/logo.png", expires, &directory_params).is_err());
+	}
+
+	#[test]
+	fn test_advanced_token() {
+		let url_signer = UrlSigner::new("secret", TokenScheme::Advanced).unwrap();
+		let expires = DateTime::from_timestamp(1700000000, 0).unwrap();
+		let before_expiry = DateTime::from_timestamp(1699999999, 0).unwrap();
+		let sign_params = SignUrlParameters{
+			remote_ip: Some("203.0.113.7".to_string()),
+			token_path: Some("/images/".to_string()),
+			countries_allowed: vec!["DE".to_string(), "NO".to_string()],
+			..Default::default()
+		};
+		let signed_url = url_signer.sign_url_until("https://cdn.example.com/images/logo.png", expires, &sign_params).unwrap();
+		assert_eq!(signed_url, "https://cdn.example.com/images/logo.png?token=DsWBAaE2OrollvKkhe2qmrXr7WkECoAV2pH4qP44cDM&token_countries=DE%2CNO&token_path=/images/&expires=1700000000");
+		assert!(url_signer.verify_url(&signed_url, Some("203.0.113.7"), before_expiry).is_ok());
+		assert!(url_signer.verify_url(&signed_url, Some("203.0.113.8"), before_expiry).is_err());
+		// The token is valid for every file below the token path
+		assert!(url_signer.verify_url(&signed_url.replace("logo.png", "icons/home.svg"), Some("203.0.113.7"), before_expiry).is_ok());
+		assert!(url_signer.verify_url(&signed_url.replace("/images/logo.png", "/videos/intro.mp4"), Some("203.0.113.7"), before_expiry).is_err());
+		let directory_params = SignUrlParameters{
+			directory_token: true,
+			..sign_params
+		};
+		let directory_signed_url = url_signer.sign_url_until("https://cdn.example.com/images/logo.png", expires, &directory_params).unwrap();
+		assert!(directory_signed_url.starts_with("https://cdn.example.com/bcdn_token=DsWBAaE2OrollvKkhe2qmrXr7WkECoAV2pH4qP44cDM&"));
+		assert!(directory_signed_url.ends_with("&expires=1700000000/images/logo.png"));
+		assert!(url_signer.verify_url(&directory_signed_url, Some("203.0.113.7"), before_expiry).is_ok());
+	}
+}